@@ -0,0 +1,164 @@
+use serde::{Serialize, Deserialize};
+use crate::globals::TokenType;
+use crate::globals::Token;
+
+// Árbol sintáctico tipado que produce el parser junto con el CST (cst.rs). El análisis
+// semántico y la generación de código trabajan sobre este árbol; la vista Syntax usa el
+// TreeNode que se obtiene del CST.
+
+// Posición del primer carácter de un nodo (línea y columna desde 1, como en los tokens)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn of(token: &Token) -> Self {
+        Span {
+            line: token.2,
+            column: token.3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ident {
+    pub name: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VarType {
+    Int,
+    Double,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Eq,
+    Neq,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Program {
+    pub items: Vec<Stmt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Stmt {
+    Main { body: Vec<Stmt>, span: Span },
+    Declaration { ty: VarType, names: Vec<Ident>, span: Span },
+    Assign { target: Ident, value: Expr, span: Span },
+    If { cond: Expr, then: Vec<Stmt>, else_: Option<Vec<Stmt>>, span: Span },
+    While { cond: Expr, body: Vec<Stmt>, span: Span },
+    DoWhile { body: Vec<Stmt>, cond: Expr, span: Span },
+    RepeatUntil { body: Vec<Stmt>, cond: Expr, span: Span },
+    // El identificador falta si el parser tuvo que recuperarse de un error
+    Read { target: Option<Ident>, span: Span },
+    Write { target: Option<Ident>, span: Span },
+    Cin { target: Option<Ident>, span: Span },
+    Cout { value: Expr, span: Span },
+    Return { value: Expr, span: Span },
+    Increment { target: Ident, span: Span },
+    Decrement { target: Ident, span: Span },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Expr {
+    Int { value: String, span: Span },
+    Real { value: String, span: Span },
+    Var(Ident),
+    // `span` es la posición del operador
+    Binary { op: BinOp, left: Box<Expr>, right: Box<Expr>, span: Span },
+    // Expresión entre paréntesis; `span` es la posición del '('
+    Group { inner: Box<Expr>, span: Span },
+    // Operando faltante que el parser insertó al recuperarse de un error
+    Error { span: Span },
+}
+
+impl BinOp {
+    pub fn from_token(token: &TokenType) -> Option<BinOp> {
+        match token {
+            TokenType::PLUS => Some(BinOp::Add),
+            TokenType::MINUS => Some(BinOp::Sub),
+            TokenType::TIMES => Some(BinOp::Mul),
+            TokenType::DIVIDE => Some(BinOp::Div),
+            TokenType::MODULO => Some(BinOp::Mod),
+            TokenType::POWER => Some(BinOp::Pow),
+            TokenType::LT => Some(BinOp::Lt),
+            TokenType::LTE => Some(BinOp::Lte),
+            TokenType::GT => Some(BinOp::Gt),
+            TokenType::GTE => Some(BinOp::Gte),
+            TokenType::EQ => Some(BinOp::Eq),
+            TokenType::NEQ => Some(BinOp::Neq),
+            TokenType::AND => Some(BinOp::And),
+            TokenType::OR => Some(BinOp::Or),
+            _ => None,
+        }
+    }
+
+    // Cómo se escribe el operador en el código
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Mod => "%",
+            BinOp::Pow => "^",
+            BinOp::Lt => "<",
+            BinOp::Lte => "<=",
+            BinOp::Gt => ">",
+            BinOp::Gte => ">=",
+            BinOp::Eq => "==",
+            BinOp::Neq => "!=",
+            BinOp::And => "&",
+            BinOp::Or => "|",
+        }
+    }
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Main { span, .. }
+            | Stmt::Declaration { span, .. }
+            | Stmt::Assign { span, .. }
+            | Stmt::If { span, .. }
+            | Stmt::While { span, .. }
+            | Stmt::DoWhile { span, .. }
+            | Stmt::RepeatUntil { span, .. }
+            | Stmt::Read { span, .. }
+            | Stmt::Write { span, .. }
+            | Stmt::Cin { span, .. }
+            | Stmt::Cout { span, .. }
+            | Stmt::Return { span, .. }
+            | Stmt::Increment { span, .. }
+            | Stmt::Decrement { span, .. } => *span,
+        }
+    }
+}
+
+impl Expr {
+    // Posición del primer carácter de la expresión; en las binarias es la del operando izquierdo
+    pub fn start(&self) -> Span {
+        match self {
+            Expr::Binary { left, .. } => left.start(),
+            Expr::Int { span, .. } | Expr::Real { span, .. } | Expr::Group { span, .. } | Expr::Error { span } => *span,
+            Expr::Var(ident) => ident.span,
+        }
+    }
+}
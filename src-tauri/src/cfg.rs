@@ -0,0 +1,382 @@
+use serde::Serialize;
+use crate::ast::{Expr, Program, Stmt};
+use crate::export;
+
+// Grafo de flujo de control: el árbol tipado (ast.rs) dividido en bloques básicos, secuencias
+// de sentencias que siempre se ejecutan completas, unidas por aristas. Un bloque que termina
+// en una condición tiene una arista `true` y una `false`; los demás, una sola arista `always`.
+// El bloque 0 es la entrada y el 1 la salida; las declaraciones no generan código y no
+// aparecen. El código inalcanzable queda en bloques sin aristas de entrada.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockKind {
+    Entry,
+    Exit,
+    Basic,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BasicBlock {
+    pub id: usize,
+    pub kind: BlockKind,
+    // Sentencias del bloque como se escriben en el código (con las palabras reservadas en inglés)
+    pub statements: Vec<String>,
+    // Línea de cada sentencia
+    pub lines: Vec<usize>,
+    // Condición con la que termina el bloque, si se ramifica
+    pub condition: Option<String>,
+    // Sentencia a la que pertenece la condición
+    pub branch: Option<Branch>,
+}
+
+// `while` se usa también para `do ... while`; con `until` la arista `true` sale del ciclo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Branch {
+    If,
+    While,
+    Until,
+}
+
+impl Branch {
+    fn keyword(self) -> &'static str {
+        match self {
+            Branch::If => "if",
+            Branch::While => "while",
+            Branch::Until => "until",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    Always,
+    True,
+    False,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Cfg {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+}
+
+pub const ENTRY: usize = 0;
+pub const EXIT: usize = 1;
+
+struct Builder {
+    cfg: Cfg,
+    // Bloque al que se agregan las sentencias; None después de un `return` o de un ciclo infinito
+    current: Option<usize>,
+}
+
+impl Builder {
+    fn block(&mut self, kind: BlockKind) -> usize {
+        let id = self.cfg.blocks.len();
+        self.cfg.blocks.push(BasicBlock { id, kind, statements: Vec::new(), lines: Vec::new(), condition: None, branch: None });
+        id
+    }
+
+    fn edge(&mut self, from: usize, to: usize, kind: EdgeKind) {
+        self.cfg.edges.push(Edge { from, to, kind });
+    }
+
+    // Bloque actual; si no hay (código inalcanzable) se empieza uno sin predecesores
+    fn current(&mut self) -> usize {
+        match self.current {
+            Some(id) => id,
+            None => {
+                let id = self.block(BlockKind::Basic);
+                self.current = Some(id);
+                id
+            }
+        }
+    }
+
+    // Empieza un bloque nuevo al que se llega desde el actual, como el encabezado de un ciclo.
+    // Si el actual todavía está vacío, se usa ese.
+    fn start_block(&mut self) -> usize {
+        if let Some(id) = self.current {
+            let block = &self.cfg.blocks[id];
+            if block.kind == BlockKind::Basic && block.statements.is_empty() && block.condition.is_none() {
+                return id;
+            }
+        }
+        let id = self.block(BlockKind::Basic);
+        if let Some(previous) = self.current {
+            self.edge(previous, id, EdgeKind::Always);
+        }
+        self.current = Some(id);
+        id
+    }
+
+    // Termina el bloque actual con la condición y devuelve su número
+    fn branch(&mut self, cond: &Expr, branch: Branch) -> usize {
+        let id = self.current();
+        self.cfg.blocks[id].condition = Some(expression(cond));
+        self.cfg.blocks[id].branch = Some(branch);
+        self.current = None;
+        id
+    }
+
+    // Une el final del bloque actual (si se alcanza) con `to`
+    fn jump(&mut self, to: usize) {
+        if let Some(from) = self.current.take() {
+            self.edge(from, to, EdgeKind::Always);
+        }
+    }
+
+    fn push(&mut self, text: String, line: usize) {
+        let id = self.current();
+        self.cfg.blocks[id].statements.push(text);
+        self.cfg.blocks[id].lines.push(line);
+    }
+
+    fn statements(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        let line = statement.span().line;
+        match statement {
+            Stmt::Main { body, .. } => self.statements(body),
+            Stmt::Declaration { .. } => {}
+            Stmt::Assign { target, value, .. } => self.push(format!("{} = {}", target.name, expression(value)), line),
+            Stmt::Read { target, .. } => self.push(format!("read {}", target.as_ref().map_or("", |t| &t.name)), line),
+            Stmt::Write { target, .. } => self.push(format!("write {}", target.as_ref().map_or("", |t| &t.name)), line),
+            Stmt::Cin { target, .. } => self.push(format!("cin {}", target.as_ref().map_or("", |t| &t.name)), line),
+            Stmt::Cout { value, .. } => self.push(format!("cout {}", expression(value)), line),
+            Stmt::Increment { target, .. } => self.push(format!("{}++", target.name), line),
+            Stmt::Decrement { target, .. } => self.push(format!("{}--", target.name), line),
+            Stmt::Return { value, .. } => {
+                self.push(format!("return {}", expression(value)), line);
+                self.jump(EXIT);
+            }
+            Stmt::If { cond, then, else_, .. } => {
+                let test = self.branch(cond, Branch::If);
+                let then_start = self.start_block();
+                self.edge(test, then_start, EdgeKind::True);
+                self.statements(then);
+                let then_end = self.current.take();
+                let else_end = match else_ {
+                    Some(else_) => {
+                        let else_start = self.start_block();
+                        self.edge(test, else_start, EdgeKind::False);
+                        self.statements(else_);
+                        self.current.take()
+                    }
+                    None => Some(test),
+                };
+                // El bloque de unión solo existe si se llega a él por alguna de las ramas
+                if then_end.is_none() && else_end.is_none() {
+                    return;
+                }
+                let join = self.block(BlockKind::Basic);
+                if let Some(then_end) = then_end {
+                    self.edge(then_end, join, EdgeKind::Always);
+                }
+                match (else_, else_end) {
+                    (None, _) => self.edge(test, join, EdgeKind::False),
+                    (Some(_), Some(else_end)) => self.edge(else_end, join, EdgeKind::Always),
+                    (Some(_), None) => {}
+                }
+                self.current = Some(join);
+            }
+            Stmt::While { cond, body, .. } => {
+                let header = self.start_block();
+                self.branch(cond, Branch::While);
+                let body_start = self.start_block();
+                self.edge(header, body_start, EdgeKind::True);
+                self.statements(body);
+                self.jump(header);
+                let after = self.block(BlockKind::Basic);
+                self.edge(header, after, EdgeKind::False);
+                self.current = Some(after);
+            }
+            // El cuerpo se ejecuta al menos una vez; la condición al final decide si se repite.
+            // En `repeat` se repite mientras la condición es falsa.
+            Stmt::DoWhile { body, cond, .. } | Stmt::RepeatUntil { body, cond, .. } => {
+                let (branch, repeat, leave) = match statement {
+                    Stmt::DoWhile { .. } => (Branch::While, EdgeKind::True, EdgeKind::False),
+                    _ => (Branch::Until, EdgeKind::False, EdgeKind::True),
+                };
+                let body_start = self.start_block();
+                self.statements(body);
+                let test = self.branch(cond, branch);
+                self.edge(test, body_start, repeat);
+                let after = self.block(BlockKind::Basic);
+                self.edge(test, after, leave);
+                self.current = Some(after);
+            }
+        }
+    }
+}
+
+// Expresión como se escribe en el código, con los paréntesis originales
+fn expression(expr: &Expr) -> String {
+    match expr {
+        Expr::Int { value, .. } | Expr::Real { value, .. } => value.clone(),
+        Expr::Var(ident) => ident.name.clone(),
+        Expr::Binary { op, left, right, .. } => format!("{} {} {}", expression(left), op.symbol(), expression(right)),
+        Expr::Group { inner, .. } => format!("({})", expression(inner)),
+        Expr::Error { .. } => "?".to_string(),
+    }
+}
+
+pub fn build(program: &Program) -> Cfg {
+    let mut builder = Builder {
+        cfg: Cfg { blocks: Vec::new(), edges: Vec::new() },
+        current: None,
+    };
+    builder.block(BlockKind::Entry);
+    builder.block(BlockKind::Exit);
+    builder.current = Some(ENTRY);
+    builder.start_block();
+    builder.statements(&program.items);
+    builder.jump(EXIT);
+    builder.cfg
+}
+
+pub fn export(cfg: &Cfg, format: &str) -> Result<String, String> {
+    match format {
+        "dot" => Ok(to_dot(cfg)),
+        "json" => serde_json::to_string_pretty(cfg).map_err(|e| e.to_string()),
+        _ => Err(format!("Formato desconocido: {}", format)),
+    }
+}
+
+// Cada bloque es una caja con sus sentencias alineadas a la izquierda y la condición al final
+pub fn to_dot(cfg: &Cfg) -> String {
+    let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=monospace];\n");
+    for block in &cfg.blocks {
+        let label = match block.kind {
+            BlockKind::Entry => "entry".to_string(),
+            BlockKind::Exit => "exit".to_string(),
+            BlockKind::Basic => {
+                let mut lines = vec![format!("B{}", block.id)];
+                lines.extend(block.statements.iter().cloned());
+                if let (Some(cond), Some(branch)) = (&block.condition, block.branch) {
+                    lines.push(format!("{} {}", branch.keyword(), cond));
+                }
+                lines.iter().map(|line| export::escape(line) + "\\l").collect()
+            }
+        };
+        let shape = if block.kind == BlockKind::Basic { "" } else { ", shape=ellipse" };
+        dot.push_str(&format!("    b{} [label=\"{}\"{}];\n", block.id, label, shape));
+    }
+    for edge in &cfg.edges {
+        let label = match edge.kind {
+            EdgeKind::Always => String::new(),
+            EdgeKind::True => " [label=\"true\"]".to_string(),
+            EdgeKind::False => " [label=\"false\"]".to_string(),
+        };
+        dot.push_str(&format!("    b{} -> b{}{};\n", edge.from, edge.to, label));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::ENGLISH;
+    use crate::locale::Locale;
+    use crate::parse;
+    use EdgeKind::{Always, False, True};
+
+    fn graph(body: &str) -> Cfg {
+        let source = format!("main() {{\n    int x;\n    cin x;\n    {}\n}}\n", body);
+        let (result, _) = parse::parse_source(&source, &ENGLISH, Locale::Es);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        build(&result.ast)
+    }
+
+    // Sentencias de cada bloque, con la condición al final como en el DOT
+    fn blocks(cfg: &Cfg) -> Vec<Vec<String>> {
+        cfg.blocks
+            .iter()
+            .map(|block| {
+                let mut lines = block.statements.clone();
+                if let (Some(cond), Some(branch)) = (&block.condition, block.branch) {
+                    lines.push(format!("{} {}", branch.keyword(), cond));
+                }
+                lines
+            })
+            .collect()
+    }
+
+    fn edges(cfg: &Cfg) -> Vec<(usize, usize, EdgeKind)> {
+        cfg.edges.iter().map(|edge| (edge.from, edge.to, edge.kind)).collect()
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn if_without_else_joins_on_false() {
+        let cfg = graph("if (x > 0) { x = 1; }\n    cout x;");
+        assert_eq!(blocks(&cfg), [lines(&[]), lines(&[]), lines(&["cin x", "if (x > 0)"]), lines(&["x = 1"]), lines(&["cout x"])]);
+        assert_eq!(edges(&cfg), [(ENTRY, 2, Always), (2, 3, True), (3, 4, Always), (2, 4, False), (4, EXIT, Always)]);
+    }
+
+    // Si las dos ramas terminan en `return` no hay bloque de unión
+    #[test]
+    fn if_else_returning_on_both_branches() {
+        let cfg = graph("if (x > 0) { return 1; } else { return 0; }");
+        assert_eq!(blocks(&cfg), [lines(&[]), lines(&[]), lines(&["cin x", "if (x > 0)"]), lines(&["return 1"]), lines(&["return 0"])]);
+        assert_eq!(edges(&cfg), [(ENTRY, 2, Always), (2, 3, True), (3, EXIT, Always), (2, 4, False), (4, EXIT, Always)]);
+    }
+
+    #[test]
+    fn while_loops_back_to_its_header() {
+        let cfg = graph("while (x > 0) { x--; }\n    cout x;");
+        assert_eq!(blocks(&cfg), [lines(&[]), lines(&[]), lines(&["cin x"]), lines(&["while (x > 0)"]), lines(&["x--"]), lines(&["cout x"])]);
+        assert_eq!(edges(&cfg), [(ENTRY, 2, Always), (2, 3, Always), (3, 4, True), (4, 3, Always), (3, 5, False), (5, EXIT, Always)]);
+    }
+
+    // Con `until` la arista `true` sale del ciclo y la `false` lo repite
+    #[test]
+    fn repeat_until_leaves_on_true() {
+        let cfg = graph("repeat { x--; } until (x == 0);\n    cout x;");
+        assert_eq!(blocks(&cfg), [lines(&[]), lines(&[]), lines(&["cin x"]), lines(&["x--", "until (x == 0)"]), lines(&["cout x"])]);
+        assert_eq!(edges(&cfg), [(ENTRY, 2, Always), (2, 3, Always), (3, 3, False), (3, 4, True), (4, EXIT, Always)]);
+    }
+
+    #[test]
+    fn code_after_return_has_no_predecessors() {
+        let cfg = graph("return x;\n    cout x;");
+        assert_eq!(blocks(&cfg), [lines(&[]), lines(&[]), lines(&["cin x", "return x"]), lines(&["cout x"])]);
+        assert_eq!(edges(&cfg), [(ENTRY, 2, Always), (2, EXIT, Always), (3, EXIT, Always)]);
+        assert!(cfg.edges.iter().all(|edge| edge.to != 3));
+    }
+
+    #[test]
+    fn dot_labels() {
+        let dot = to_dot(&graph("if (x > 0) { x = 1; }\n    while (x < 9) { x++; }\n    repeat { x--; } until (x == 0);"));
+        for line in [
+            "    b0 [label=\"entry\", shape=ellipse];\n",
+            "    b1 [label=\"exit\", shape=ellipse];\n",
+            "    b2 [label=\"B2\\lcin x\\lif (x > 0)\\l\"];\n",
+            "    b3 [label=\"B3\\lx = 1\\l\"];\n",
+            "    b4 [label=\"B4\\lwhile (x < 9)\\l\"];\n",
+            "    b2 -> b3 [label=\"true\"];\n",
+            "    b2 -> b4 [label=\"false\"];\n",
+            "    b0 -> b2;\n",
+        ] {
+            assert!(dot.contains(line), "falta {:?} en:\n{}", line, dot);
+        }
+        assert!(dot.contains("\\luntil (x == 0)\\l\"];\n"), "{}", dot);
+    }
+}
@@ -0,0 +1,351 @@
+use std::fs;
+use crate::codes;
+use crate::dialect::{self, Dialect};
+use crate::format::{self, BraceStyle, FormatOptions};
+use crate::locale::Locale;
+use crate::cst::{self, SyntaxNode};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::semantic::{self, SemanticOptions};
+use crate::{cfg, export, grammar, parse};
+
+const USAGE: &str = "Uso:
+    app                     abre el editor
+    app --explain <código>  explica un código de error (p. ej. P004)
+    app --explain           lista los códigos de error
+    app format <archivo>    escribe el programa formateado en la salida estándar
+    app --tree <formato> <archivo>
+                            imprime el árbol sintáctico como dot, sexpr o text
+    app --cfg <dot|json> <archivo>
+                            imprime el grafo de flujo de control del programa
+    app --annotate <archivo>
+                            imprime el árbol anotado con el tipo y el valor de cada
+                            expresión, y los errores semánticos
+    app --grammar <ebnf|railroad|check>
+                            imprime la gramática o comprueba que es LL(1) y que el
+                            parser acepta las oraciones generadas con ella
+
+Opciones:
+    --locale <es|en>        idioma de los mensajes (por defecto, español)
+    --dialect <nombre>      dialecto de las palabras reservadas o ruta a su JSON
+
+Opciones de format:
+    --indent <n>            espacios por nivel de sangría (por defecto, 4)
+    --braces <estilo>       same-line (por defecto) o next-line
+    --write                 reemplaza el archivo en lugar de imprimirlo
+
+Opciones de --annotate:
+    --allow-shadowing       no advierte cuando una variable oculta a otra";
+
+// Subcomandos que se ejecutan sin abrir la ventana
+const COMMANDS: [&str; 8] = ["--explain", "--tree", "--cfg", "--annotate", "--grammar", "format", "--help", "-h"];
+
+// Ejecuta las opciones de línea de comandos sin abrir la ventana. Devuelve el código de
+// salida del proceso, o None si no hay un subcomando y se debe iniciar la aplicación: el
+// sistema también pasa argumentos propios, como `-psn_...` en macOS o la ruta del archivo
+// con el que se abrió la aplicación.
+pub fn run(args: &[String]) -> Option<i32> {
+    if !args.iter().any(|arg| COMMANDS.contains(&arg.as_str())) {
+        return None;
+    }
+    attach_console();
+    let mut args = args.to_vec();
+    let locale = match take_option(&mut args, "--locale") {
+        Ok(name) => Locale::from_name(name.as_deref()),
+        Err(e) => Err(e),
+    };
+    let locale = match locale {
+        Ok(locale) => locale,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Some(2);
+        }
+    };
+    let status = match args.first().map(String::as_str) {
+        Some("--explain") => explain(args.get(1), locale),
+        Some("--tree") => export_tree(&mut args),
+        Some("--cfg") => export_cfg(&mut args),
+        Some("--annotate") => annotate(&mut args, locale),
+        Some("--grammar") => show_grammar(args.get(1).map(String::as_str), locale),
+        Some("format") => format_file(&mut args, locale),
+        Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            0
+        }
+        Some(other) => {
+            eprintln!("Opción desconocida: {}\n{}", other, USAGE);
+            2
+        }
+        None => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    };
+    Some(status)
+}
+
+// En Windows la versión de release no tiene consola (ver `windows_subsystem` en main.rs), así
+// que la salida se escribe en la de la terminal desde la que se ejecutó el programa
+#[cfg(windows)]
+fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Falla si ya hay una consola (versión de depuración) o no hay terminal; en ambos casos se sigue igual
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+// Quita `--nombre valor` de los argumentos y devuelve el valor
+fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, String> {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) if i + 1 < args.len() => {
+            let value = args.remove(i + 1);
+            args.remove(i);
+            Ok(Some(value))
+        }
+        Some(_) => Err(format!("Falta el valor de {}", name)),
+        None => Ok(None),
+    }
+}
+
+// Quita `--dialect nombre` de los argumentos; sin la opción se usa el inglés
+fn take_dialect(args: &mut Vec<String>) -> Result<Dialect, String> {
+    match take_option(args, "--dialect")? {
+        Some(name_or_path) => Dialect::load(&name_or_path),
+        None => Ok(dialect::ENGLISH.clone()),
+    }
+}
+
+fn read_source(path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(source) => Some(source),
+        Err(e) => {
+            eprintln!("Error al leer {}: {}", path, e);
+            None
+        }
+    }
+}
+
+// Quita `--nombre` de los argumentos y dice si estaba
+fn take_flag(args: &mut Vec<String>, name: &str) -> bool {
+    match args.iter().position(|arg| arg == name) {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+fn format_options(args: &mut Vec<String>) -> Result<FormatOptions, String> {
+    let mut options = FormatOptions::default();
+    if let Some(indent) = take_option(args, "--indent")? {
+        options.indent_width = indent.parse().map_err(|_| format!("Sangría inválida: {}", indent))?;
+    }
+    if let Some(style) = take_option(args, "--braces")? {
+        options.brace_style = BraceStyle::from_name(&style)?;
+    }
+    Ok(options)
+}
+
+// `app format <archivo>`: con errores no se formatea y se reportan con su línea señalada
+fn format_file(args: &mut Vec<String>, locale: Locale) -> i32 {
+    let write = take_flag(args, "--write");
+    let (dialect, options) = match (take_dialect(args), format_options(args)) {
+        (Ok(dialect), Ok(options)) => (dialect, options),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let path = match args.as_slice() {
+        [_, path] => path,
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let source = match read_source(path) {
+        Some(source) => source,
+        None => return 1,
+    };
+    match format::format(&source, &dialect, &options, locale) {
+        Ok(formatted) if write => match fs::write(path, formatted) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Error al guardar el archivo: {}", e);
+                1
+            }
+        },
+        Ok(formatted) => {
+            print!("{}", formatted);
+            0
+        }
+        Err(errors) => {
+            eprintln!("{}", format::NOT_FORMATTED.get(locale));
+            for error in errors {
+                eprintln!("{}", error.render());
+            }
+            1
+        }
+    }
+}
+
+// `app --tree <formato> <archivo>`: el árbol se imprime aunque el programa tenga errores
+fn export_tree(args: &mut Vec<String>) -> i32 {
+    let dialect = match take_dialect(args) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let (format, path) = match args.as_slice() {
+        [_, format, path] => (format, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let source = match read_source(path) {
+        Some(source) => source,
+        None => return 1,
+    };
+    let (result, _) = parse::parse_source(&source, &dialect, Locale::Es);
+    match export::export(&cst::tree_view(&SyntaxNode::new_root(result.green)), format) {
+        Ok(tree) => {
+            print!("{}", tree);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
+
+// `app --cfg <formato> <archivo>`: como --tree, el grafo se arma aunque haya errores
+fn export_cfg(args: &mut Vec<String>) -> i32 {
+    let dialect = match take_dialect(args) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let (format, path) = match args.as_slice() {
+        [_, format, path] => (format, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let source = match read_source(path) {
+        Some(source) => source,
+        None => return 1,
+    };
+    let (result, _) = parse::parse_source(&source, &dialect, Locale::Es);
+    match cfg::export(&cfg::build(&result.ast), format) {
+        Ok(output) => {
+            print!("{}", output);
+            if !output.ends_with('\n') {
+                println!();
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
+}
+
+// `app --annotate <archivo>`: el árbol va a la salida estándar y los diagnósticos a la de
+// errores; termina con 1 si hay errores de sintaxis o semánticos (no solo advertencias)
+fn annotate(args: &mut Vec<String>, locale: Locale) -> i32 {
+    let options = SemanticOptions { warn_shadowing: !take_flag(args, "--allow-shadowing") };
+    let dialect = match take_dialect(args) {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let path = match args.as_slice() {
+        [_, path] => path,
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let source = match read_source(path) {
+        Some(source) => source,
+        None => return 1,
+    };
+    let (result, lexical) = parse::parse_source(&source, &dialect, locale);
+    let analysis = semantic::analyze(&result.ast, &options, locale);
+    print!("{}", export::to_text(&semantic::annotate(&SyntaxNode::new_root(result.green), &analysis.types)));
+    let lexical: Vec<Diagnostic> = lexical.iter().map(|error| Diagnostic::lexical(error, locale)).collect();
+    for diagnostic in lexical.iter().chain(&result.errors).chain(&analysis.diagnostics) {
+        eprintln!("{}", diagnostic.clone().with_snippet(&source).render());
+    }
+    let failed = !lexical.is_empty() || !result.errors.is_empty() || analysis.diagnostics.iter().any(|d| d.severity == Severity::Error);
+    i32::from(failed)
+}
+
+// `app --grammar check` termina con 1 si hay conflictos u oraciones rechazadas; las pruebas
+// de grammar.rs hacen la misma comprobación con `cargo test`
+fn show_grammar(format: Option<&str>, locale: Locale) -> i32 {
+    let output = match format {
+        Some("ebnf") => Ok(grammar::SOURCE.to_string()),
+        Some("railroad") => grammar::to_railroad(grammar::SOURCE).map(|json| json + "\n"),
+        Some("check") => match grammar::check(grammar::SOURCE, locale) {
+            Ok(report) => {
+                print!("{}", grammar::render(&report));
+                return if report.is_ok() { 0 } else { 1 };
+            }
+            Err(e) => Err(e),
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    match output {
+        Ok(output) => {
+            print!("{}", output);
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn explain(code: Option<&String>, locale: Locale) -> i32 {
+    match code {
+        Some(code) => match codes::explain(code) {
+            Some(entry) => {
+                print!("{}", entry.render(locale));
+                0
+            }
+            None => {
+                eprintln!("Código de error desconocido: {}", code);
+                1
+            }
+        },
+        None => {
+            for entry in codes::all() {
+                println!("{}  {}", entry.code, entry.title.get(locale));
+            }
+            0
+        }
+    }
+}
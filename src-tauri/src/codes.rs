@@ -0,0 +1,839 @@
+use serde::Serialize;
+use crate::locale::{Locale, Text};
+use crate::diagnostic::Severity;
+
+// Catálogo de códigos de error. El prefijo indica la fase que reporta el error:
+// L = léxico, P = sintáctico (parser), S = semántico. Los códigos son estables:
+// no se reutilizan ni se renumeran aunque un error deje de reportarse.
+// `message` es la plantilla del mensaje corto; sus marcadores {nombre} los llena quien reporta el error.
+#[derive(Debug, Clone)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub title: Text,
+    pub message: Text,
+    pub explanation: Text,
+    pub example: &'static str,
+    pub fix: &'static str,
+}
+
+// Entrada del catálogo traducida, como se envía al front-end
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub title: &'static str,
+    pub explanation: &'static str,
+    pub example: &'static str,
+    pub fix: &'static str,
+}
+
+const EXAMPLE: Text = Text {
+    es: "Ejemplo con error:",
+    en: "Erroneous example:",
+};
+
+const FIX: Text = Text {
+    es: "Corrección:",
+    en: "Fix:",
+};
+
+pub const UNKNOWN_CHARACTER: &str = "L001";
+pub const MALFORMED_REAL: &str = "L002";
+pub const LONE_BANG: &str = "L003";
+pub const UNTERMINATED_COMMENT: &str = "L004";
+
+pub const UNEXPECTED_TOKEN: &str = "P001";
+pub const MISSING_PAREN: &str = "P002";
+pub const MISSING_BRACE: &str = "P003";
+pub const MISSING_SEMICOLON: &str = "P004";
+pub const MISSING_IDENTIFIER: &str = "P005";
+pub const MISSING_OPERAND: &str = "P006";
+pub const COLON_OUTSIDE_CASE: &str = "P007";
+pub const ASSIGNMENT_TARGET: &str = "P008";
+pub const MISSING_KEYWORD: &str = "P009";
+pub const MISSING_ASSIGN: &str = "P010";
+
+pub const UNDECLARED_VARIABLE: &str = "S001";
+pub const REDECLARED_VARIABLE: &str = "S002";
+pub const UNUSED_VARIABLE: &str = "S003";
+pub const UNREAD_VARIABLE: &str = "S004";
+pub const MODULO_ON_REAL: &str = "S005";
+pub const NARROWING_ASSIGNMENT: &str = "S006";
+pub const CONDITION_NOT_BOOLEAN: &str = "S007";
+pub const LOGICAL_OPERAND_NOT_BOOLEAN: &str = "S008";
+pub const DIVISION_BY_ZERO: &str = "S009";
+pub const INTEGER_OVERFLOW: &str = "S010";
+pub const UNINITIALIZED_VARIABLE: &str = "S011";
+pub const UNREACHABLE_CODE: &str = "S012";
+pub const LOOP_NEVER_RUNS: &str = "S013";
+pub const MISSING_MAIN: &str = "S014";
+pub const DUPLICATE_MAIN: &str = "S015";
+pub const STATEMENT_OUTSIDE_MAIN: &str = "S016";
+pub const NESTED_MAIN: &str = "S017";
+pub const SHADOWED_VARIABLE: &str = "S018";
+
+const CATALOG: [ErrorCode; 32] = [
+    ErrorCode {
+        code: UNKNOWN_CHARACTER,
+        severity: Severity::Error,
+        title: Text {
+            es: "carácter desconocido",
+            en: "unknown character",
+        },
+        message: Text {
+            es: "carácter desconocido '{lexeme}'",
+            en: "unknown character '{lexeme}'",
+        },
+        explanation: Text {
+            es: "El escáner encontró un carácter que no forma parte de ningún token del lenguaje, \
+por ejemplo '@', '#' o '$'. El carácter se descarta y el análisis continúa con el siguiente.",
+            en: "The scanner found a character that is not part of any token of the language, \
+such as '@', '#' or '$'. The character is discarded and scanning continues with the next one.",
+        },
+        example: "int total@;",
+        fix: "int total;",
+    },
+    ErrorCode {
+        code: MALFORMED_REAL,
+        severity: Severity::Error,
+        title: Text {
+            es: "número real incompleto",
+            en: "incomplete real number",
+        },
+        message: Text {
+            es: "número real incompleto '{lexeme}'",
+            en: "incomplete real number '{lexeme}'",
+        },
+        explanation: Text {
+            es: "Un número real necesita al menos un dígito después del punto decimal. \
+Un número seguido de '.' sin dígitos no es un literal válido.",
+            en: "A real number needs at least one digit after the decimal point. \
+A number followed by '.' with no digits is not a valid literal.",
+        },
+        example: "x = 3.;",
+        fix: "x = 3.0;",
+    },
+    ErrorCode {
+        code: LONE_BANG,
+        severity: Severity::Error,
+        title: Text {
+            es: "'!' sin '='",
+            en: "'!' without '='",
+        },
+        message: Text {
+            es: "'!' sin '=' no es un operador",
+            en: "'!' without '=' is not an operator",
+        },
+        explanation: Text {
+            es: "El lenguaje no tiene operador de negación: '!' solo es válido como parte \
+del operador de desigualdad '!='.",
+            en: "The language has no negation operator: '!' is only valid as part \
+of the inequality operator '!='.",
+        },
+        example: "if (!x) { y = 1; }",
+        fix: "if (x != 0) { y = 1; }",
+    },
+    ErrorCode {
+        code: UNTERMINATED_COMMENT,
+        severity: Severity::Error,
+        title: Text {
+            es: "comentario sin cerrar",
+            en: "unterminated comment",
+        },
+        message: Text {
+            es: "comentario '/*' sin cerrar",
+            en: "unterminated '/*' comment",
+        },
+        explanation: Text {
+            es: "Un comentario múltiple abierto con '/*' llegó al fin de archivo sin su '*/'. \
+Los comentarios múltiples se pueden anidar, así que cada '/*' necesita su propio '*/'. \
+El error se reporta en la posición del '/*' que quedó abierto.",
+            en: "A multi-line comment opened with '/*' reached the end of file without its '*/'. \
+Multi-line comments nest, so every '/*' needs its own '*/'. \
+The error is reported at the '/*' that was left open.",
+        },
+        example: "/* cálculo /* auxiliar */\nx = 1;",
+        fix: "/* cálculo /* auxiliar */ */\nx = 1;",
+    },
+    ErrorCode {
+        code: UNEXPECTED_TOKEN,
+        severity: Severity::Error,
+        title: Text {
+            es: "token inesperado",
+            en: "unexpected token",
+        },
+        message: Text {
+            es: "se esperaba {expected}{after}, se encontró {found}",
+            en: "expected {expected}{after}, found {found}",
+        },
+        explanation: Text {
+            es: "El token no puede iniciar una sentencia en esta posición. Una sentencia empieza \
+con una palabra reservada (if, while, do, repeat, int, double, cin, cout, ...) o con un \
+identificador seguido de '=', '++' o '--'. El parser descarta tokens hasta el siguiente ';', '}' o \
+inicio de sentencia y continúa desde ahí.",
+            en: "The token cannot start a statement at this position. A statement starts \
+with a keyword (if, while, do, repeat, int, double, cin, cout, ...) or with an \
+identifier followed by '=', '++' or '--'. The parser skips tokens up to the next ';', '}' or \
+statement start and continues from there.",
+        },
+        example: "main() { else { x = 1; } }",
+        fix: "main() { if (x > 0) { x = 1; } else { x = 2; } }",
+    },
+    ErrorCode {
+        code: MISSING_PAREN,
+        severity: Severity::Error,
+        title: Text {
+            es: "falta un paréntesis",
+            en: "missing parenthesis",
+        },
+        message: Text {
+            es: "se esperaba {expected}{after}, se encontró {found}",
+            en: "expected {expected}{after}, found {found}",
+        },
+        explanation: Text {
+            es: "Se esperaba '(' o ')'. Las condiciones de if, while y until van entre paréntesis, \
+'main' va seguido de '()' y cada '(' de una expresión necesita su ')'.",
+            en: "Expected '(' or ')'. The conditions of if, while and until go between parentheses, \
+'main' is followed by '()' and every '(' in an expression needs its ')'.",
+        },
+        example: "if (x > 2 { y = 1; }",
+        fix: "if (x > 2) { y = 1; }",
+    },
+    ErrorCode {
+        code: MISSING_BRACE,
+        severity: Severity::Error,
+        title: Text {
+            es: "falta una llave",
+            en: "missing brace",
+        },
+        message: Text {
+            es: "se esperaba {expected}{after}, se encontró {found}",
+            en: "expected {expected}{after}, found {found}",
+        },
+        explanation: Text {
+            es: "Se esperaba '{' o '}'. El cuerpo de main es un bloque entre llaves; los de if, \
+else, while, do y repeat son un bloque o una sola sentencia sin llaves (así se escribe \
+`else if`). Un bloque sin '}' se extiende hasta el fin de archivo.",
+            en: "Expected '{' or '}'. The body of main is a block between braces; the bodies of \
+if, else, while, do and repeat are a block or a single statement without braces (which is \
+how `else if` is written). A block without '}' extends to the end of file.",
+        },
+        example: "while (x < 10) { x++;\n",
+        fix: "while (x < 10) { x++; }\n",
+    },
+    ErrorCode {
+        code: MISSING_SEMICOLON,
+        severity: Severity::Error,
+        title: Text {
+            es: "falta ';'",
+            en: "missing ';'",
+        },
+        message: Text {
+            es: "se esperaba {expected}{after}, se encontró {found}",
+            en: "expected {expected}{after}, found {found}",
+        },
+        explanation: Text {
+            es: "Las declaraciones, asignaciones, incrementos, entradas y salidas terminan en ';'. \
+El error se señala en el token que sigue a la sentencia incompleta, que suele estar en la línea \
+siguiente.",
+            en: "Declarations, assignments, increments, input and output end in ';'. \
+The error points at the token that follows the incomplete statement, which is usually on the \
+next line.",
+        },
+        example: "int x, y\nx = 3;",
+        fix: "int x, y;\nx = 3;",
+    },
+    ErrorCode {
+        code: MISSING_IDENTIFIER,
+        severity: Severity::Error,
+        title: Text {
+            es: "falta un identificador",
+            en: "missing identifier",
+        },
+        message: Text {
+            es: "se esperaba {expected}{after}, se encontró {found}",
+            en: "expected {expected}{after}, found {found}",
+        },
+        explanation: Text {
+            es: "Se esperaba el nombre de una variable: después de int o double, en cin, read y \
+write, y antes de '=', '++' o '--'. Un identificador empieza con una letra o '_' y no puede ser \
+una palabra reservada.",
+            en: "Expected a variable name: after int or double, in cin, read and \
+write, and before '=', '++' or '--'. An identifier starts with a letter or '_' and cannot be \
+a keyword.",
+        },
+        example: "int x, ;",
+        fix: "int x, y;",
+    },
+    ErrorCode {
+        code: MISSING_OPERAND,
+        severity: Severity::Error,
+        title: Text {
+            es: "falta un operando",
+            en: "missing operand",
+        },
+        message: Text {
+            es: "se esperaba {expected}{after}, se encontró {found}",
+            en: "expected {expected}{after}, found {found}",
+        },
+        explanation: Text {
+            es: "Un operador binario necesita una expresión a cada lado, y una condición o un \
+valor asignado no puede estar vacío. Un operando es un número, un identificador o una expresión \
+entre paréntesis.",
+            en: "A binary operator needs an expression on each side, and a condition or an \
+assigned value cannot be empty. An operand is a number, an identifier or an expression \
+between parentheses.",
+        },
+        example: "x = 3 +;",
+        fix: "x = 3 + 1;",
+    },
+    ErrorCode {
+        code: COLON_OUTSIDE_CASE,
+        severity: Severity::Error,
+        title: Text {
+            es: "':' fuera de un case",
+            en: "':' outside of a case",
+        },
+        message: Text {
+            es: "token ':' fuera de un case",
+            en: "':' outside of a case",
+        },
+        explanation: Text {
+            es: "':' solo aparece después de la etiqueta de un case. Fuera de un case no inicia \
+ninguna sentencia.",
+            en: "':' only appears after the label of a case. Outside of a case it does not start \
+any statement.",
+        },
+        example: "x = 1;\n: y = 2;",
+        fix: "x = 1;\ny = 2;",
+    },
+    ErrorCode {
+        code: ASSIGNMENT_TARGET,
+        severity: Severity::Error,
+        title: Text {
+            es: "asignación a algo que no es una variable",
+            en: "assignment to something that is not a variable",
+        },
+        message: Text {
+            es: "se esperaba una asignación a un identificador antes de {found}",
+            en: "expected an assignment to an identifier before {found}",
+        },
+        explanation: Text {
+            es: "Una sentencia no puede empezar con un número o una expresión entre paréntesis. \
+Solo se puede asignar a un identificador, que va a la izquierda del '='.",
+            en: "A statement cannot start with a number or a parenthesized expression. \
+Only an identifier can be assigned to, and it goes on the left of the '='.",
+        },
+        example: "5 = x;",
+        fix: "x = 5;",
+    },
+    ErrorCode {
+        code: MISSING_KEYWORD,
+        severity: Severity::Error,
+        title: Text {
+            es: "falta una palabra reservada",
+            en: "missing keyword",
+        },
+        message: Text {
+            es: "se esperaba {expected}{after}, se encontró {found}",
+            en: "expected {expected}{after}, found {found}",
+        },
+        explanation: Text {
+            es: "Falta una palabra reservada que la estructura exige, como el 'while' que cierra \
+un do o el 'until' que cierra un repeat. En un dialecto distinto del inglés se espera la palabra \
+de ese dialecto.",
+            en: "A keyword required by the construct is missing, such as the 'while' that closes \
+a do or the 'until' that closes a repeat. In a dialect other than English the keyword \
+of that dialect is expected.",
+        },
+        example: "do { x++; } (x < 10);",
+        fix: "do { x++; } while (x < 10);",
+    },
+    ErrorCode {
+        code: MISSING_ASSIGN,
+        severity: Severity::Error,
+        title: Text {
+            es: "falta '=', '++' o '--'",
+            en: "missing '=', '++' or '--'",
+        },
+        message: Text {
+            es: "se esperaba {expected}{after}, se encontró {found}",
+            en: "expected {expected}{after}, found {found}",
+        },
+        explanation: Text {
+            es: "Una sentencia que empieza con un identificador es una asignación, un incremento \
+o un decremento, así que el identificador debe ir seguido de '=', '++' o '--'.",
+            en: "A statement that starts with an identifier is an assignment, an increment \
+or a decrement, so the identifier must be followed by '=', '++' or '--'.",
+        },
+        example: "x 5;",
+        fix: "x = 5;",
+    },
+    ErrorCode {
+        code: UNDECLARED_VARIABLE,
+        severity: Severity::Error,
+        title: Text {
+            es: "variable no declarada",
+            en: "undeclared variable",
+        },
+        message: Text {
+            es: "la variable '{name}' no está declarada antes de este uso",
+            en: "variable '{name}' is not declared before this use",
+        },
+        explanation: Text {
+            es: "Toda variable debe declararse con `int` o `double` antes de usarse, en el mismo \
+ámbito o en uno que lo contenga. Una declaración que aparece después del uso no cuenta.",
+            en: "Every variable must be declared with `int` or `double` before it is used, in the \
+same scope or in an enclosing one. A declaration that appears after the use does not count.",
+        },
+        example: "main() {\n    x = 1;\n    cout x;\n}\n",
+        fix: "main() {\n    int x;\n    x = 1;\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: REDECLARED_VARIABLE,
+        severity: Severity::Error,
+        title: Text {
+            es: "variable declarada dos veces",
+            en: "variable declared twice",
+        },
+        message: Text {
+            es: "la variable '{name}' ya está declarada en este ámbito (línea {line})",
+            en: "variable '{name}' is already declared in this scope (line {line})",
+        },
+        explanation: Text {
+            es: "Un nombre solo puede declararse una vez en cada ámbito, aunque las dos \
+declaraciones tengan el mismo tipo. Los usos siguientes se refieren a la primera declaración.",
+            en: "A name can be declared only once in each scope, even if both declarations \
+have the same type. Later uses refer to the first declaration.",
+        },
+        example: "main() {\n    int x;\n    double x;\n    x = 1;\n    cout x;\n}\n",
+        fix: "main() {\n    int x;\n    double y;\n    x = 1;\n    y = x;\n    cout y;\n}\n",
+    },
+    ErrorCode {
+        code: UNUSED_VARIABLE,
+        severity: Severity::Warning,
+        title: Text {
+            es: "variable sin usar",
+            en: "unused variable",
+        },
+        message: Text {
+            es: "la variable '{name}' se declara pero nunca se usa",
+            en: "variable '{name}' is declared but never used",
+        },
+        explanation: Text {
+            es: "La variable no aparece en ninguna sentencia después de su declaración. Suele \
+ser un resto de código anterior o un error de escritura en otro nombre.",
+            en: "The variable does not appear in any statement after its declaration. It is \
+usually left over from earlier code or a typo in another name.",
+        },
+        example: "main() {\n    int x, y;\n    x = 1;\n    cout x;\n}\n",
+        fix: "main() {\n    int x;\n    x = 1;\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: UNREAD_VARIABLE,
+        severity: Severity::Warning,
+        title: Text {
+            es: "variable asignada que nunca se lee",
+            en: "variable assigned but never read",
+        },
+        message: Text {
+            es: "a la variable '{name}' se le asigna un valor que nunca se lee",
+            en: "variable '{name}' is assigned a value that is never read",
+        },
+        explanation: Text {
+            es: "La variable solo se escribe (con una asignación, `cin` o `read`) y ninguna \
+expresión, `cout` o `write` la lee, así que el valor asignado se pierde.",
+            en: "The variable is only written (by an assignment, `cin` or `read`) and no \
+expression, `cout` or `write` reads it, so the assigned value is lost.",
+        },
+        example: "main() {\n    int x;\n    x = 1;\n}\n",
+        fix: "main() {\n    int x;\n    x = 1;\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: MODULO_ON_REAL,
+        severity: Severity::Error,
+        title: Text {
+            es: "'%' con un operando real",
+            en: "'%' with a real operand",
+        },
+        message: Text {
+            es: "el operador '%' necesita operandos enteros, pero el operando {side} es double",
+            en: "operator '%' needs integer operands, but the {side} one is double",
+        },
+        explanation: Text {
+            es: "El resto de la división solo está definido entre enteros. Si el valor es \
+entero, guárdelo en una variable `int`; si no, el cálculo necesita otra operación.",
+            en: "The remainder of a division is only defined between integers. If the value is \
+whole, store it in an `int` variable; otherwise the computation needs another operation.",
+        },
+        example: "main() {\n    double a;\n    int b;\n    a = 7.5;\n    b = 2;\n    cout a % b;\n}\n",
+        fix: "main() {\n    int a, b;\n    a = 7;\n    b = 2;\n    cout a % b;\n}\n",
+    },
+    ErrorCode {
+        code: NARROWING_ASSIGNMENT,
+        severity: Severity::Warning,
+        title: Text {
+            es: "asignación de double a int",
+            en: "double assigned to int",
+        },
+        message: Text {
+            es: "se asigna un valor double a la variable int '{name}'; se pierde la parte decimal",
+            en: "a double value is assigned to int variable '{name}'; the fractional part is lost",
+        },
+        explanation: Text {
+            es: "En una operación entre `int` y `double` el `int` se convierte a `double`, así \
+que el resultado es `double`. Al guardarlo en una variable `int` se trunca hacia cero.",
+            en: "In an operation between `int` and `double` the `int` is converted to `double`, \
+so the result is `double`. Storing it in an `int` variable truncates it toward zero.",
+        },
+        example: "main() {\n    int x;\n    x = 7 / 2.0;\n    cout x;\n}\n",
+        fix: "main() {\n    double x;\n    x = 7 / 2.0;\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: CONDITION_NOT_BOOLEAN,
+        severity: Severity::Error,
+        title: Text {
+            es: "condición que no es booleana",
+            en: "non-boolean condition",
+        },
+        message: Text {
+            es: "la condición es de tipo double; debe ser una comparación o un valor entero",
+            en: "the condition has type double; it must be a comparison or an integer value",
+        },
+        explanation: Text {
+            es: "Las condiciones de `if`, `while` y `until` deben ser booleanas: una comparación, \
+una operación `&` o `|`, o un `int` que se toma como falso si vale 0. Un `double` no se acepta \
+porque compararlo con 0 rara vez es lo que se quiere.",
+            en: "The conditions of `if`, `while` and `until` must be boolean: a comparison, an \
+`&` or `|` operation, or an `int`, which is false when it is 0. A `double` is not accepted \
+because comparing it with 0 is rarely what was meant.",
+        },
+        example: "main() {\n    double x;\n    x = 0.5;\n    while x {\n        x = x - 0.1;\n    }\n    cout x;\n}\n",
+        fix: "main() {\n    double x;\n    x = 0.5;\n    while x > 0 {\n        x = x - 0.1;\n    }\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: LOGICAL_OPERAND_NOT_BOOLEAN,
+        severity: Severity::Error,
+        title: Text {
+            es: "operando lógico que no es booleano",
+            en: "non-boolean logical operand",
+        },
+        message: Text {
+            es: "el operando {side} de '{operator}' es de tipo double; debe ser booleano o entero",
+            en: "the {side} operand of '{operator}' has type double; it must be boolean or integer",
+        },
+        explanation: Text {
+            es: "Los operadores `&` y `|` combinan condiciones, así que sus operandos siguen las \
+mismas reglas que la condición de un `if`: comparaciones, otras operaciones lógicas o enteros.",
+            en: "The `&` and `|` operators combine conditions, so their operands follow the same \
+rules as the condition of an `if`: comparisons, other logical operations or integers.",
+        },
+        example: "main() {\n    double x;\n    int y;\n    x = 1.5;\n    y = 2;\n    cout x & (y > 1);\n}\n",
+        fix: "main() {\n    double x;\n    int y;\n    x = 1.5;\n    y = 2;\n    cout (x > 1) & (y > 1);\n}\n",
+    },
+    ErrorCode {
+        code: DIVISION_BY_ZERO,
+        severity: Severity::Error,
+        title: Text {
+            es: "división por cero",
+            en: "division by zero",
+        },
+        message: Text {
+            es: "el divisor de '{operator}' es cero",
+            en: "the divisor of '{operator}' is zero",
+        },
+        explanation: Text {
+            es: "El compilador evalúa las expresiones formadas solo por números. Dividir entre \
+cero, calcular el resto `% 0` o elevar cero a una potencia negativa no tiene resultado, así que \
+el programa fallaría al llegar a esa línea. Basta con que el divisor sea constante: `x / 0` es \
+un error aunque `x` sea una variable.",
+            en: "The compiler evaluates expressions made only of numbers. Dividing by zero, \
+taking the remainder `% 0` or raising zero to a negative power has no result, so the program \
+would fail when it reached that line. Only the divisor needs to be constant: `x / 0` is an error \
+even when `x` is a variable.",
+        },
+        example: "main() {\n    int x;\n    x = 10 / (2 - 2);\n    cout x;\n}\n",
+        fix: "main() {\n    int x;\n    x = 10 / (2 + 2);\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: INTEGER_OVERFLOW,
+        severity: Severity::Warning,
+        title: Text {
+            es: "desbordamiento de entero",
+            en: "integer overflow",
+        },
+        message: Text {
+            es: "el valor no cabe en un int de 32 bits",
+            en: "the value does not fit in a 32-bit int",
+        },
+        explanation: Text {
+            es: "Un `int` guarda valores entre -2147483648 y 2147483647. Un literal mayor o una \
+operación entre constantes cuyo resultado sale de ese rango da otro valor al ejecutarse. Si se \
+necesitan números más grandes, use `double`.",
+            en: "An `int` holds values between -2147483648 and 2147483647. A larger literal or \
+an operation between constants whose result leaves that range gives another value at run time. \
+If larger numbers are needed, use `double`.",
+        },
+        example: "main() {\n    int x;\n    x = 65536 * 65536;\n    cout x;\n}\n",
+        fix: "main() {\n    double x;\n    x = 65536.0 * 65536;\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: UNINITIALIZED_VARIABLE,
+        severity: Severity::Warning,
+        title: Text {
+            es: "variable que puede leerse sin valor",
+            en: "variable possibly read without a value",
+        },
+        message: Text {
+            es: "la variable '{name}' puede leerse antes de que se le asigne un valor",
+            en: "variable '{name}' may be read before it is assigned a value",
+        },
+        explanation: Text {
+            es: "Las variables locales no empiezan con ningún valor: hay que asignarlas, o leerlas \
+con `cin` o `read`, antes de usarlas. Se avisa si algún camino llega a la lectura sin pasar por \
+una asignación, por ejemplo si solo una rama del `if` asigna la variable o si la asignación está \
+dentro de un `while` que puede no ejecutarse. Las variables globales, declaradas fuera de \
+main, empiezan en 0 como en C y nunca producen este aviso.",
+            en: "Local variables do not start with any value: they must be assigned, or read \
+with `cin` or `read`, before they are used. The warning appears if some path reaches the read \
+without going through an assignment, for example if only one branch of the `if` assigns the \
+variable or if the assignment is inside a `while` that may not run. Global variables, declared \
+outside main, start at 0 as in C and never produce this warning.",
+        },
+        example: "main() {\n    int x, y;\n    cin y;\n    if y > 0 {\n        x = 1;\n    }\n    cout x;\n}\n",
+        fix: "main() {\n    int x, y;\n    cin y;\n    if y > 0 {\n        x = 1;\n    } else {\n        x = 0;\n    }\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: UNREACHABLE_CODE,
+        severity: Severity::Warning,
+        title: Text {
+            es: "código inalcanzable",
+            en: "unreachable code",
+        },
+        message: Text {
+            es: "esta sentencia nunca se ejecuta",
+            en: "this statement is never executed",
+        },
+        explanation: Text {
+            es: "Ningún camino del programa llega a esta sentencia: está después de un `return`, \
+de un `if` cuyas dos ramas terminan con `return` o de un ciclo cuya condición constante hace \
+que nunca termine. Se reporta solo la primera sentencia de cada bloque.",
+            en: "No path through the program reaches this statement: it follows a `return`, an \
+`if` whose two branches end with `return`, or a loop whose constant condition makes it never \
+finish. Only the first statement of each block is reported.",
+        },
+        example: "main() {\n    int x;\n    x = 1;\n    return x;\n    cout x;\n}\n",
+        fix: "main() {\n    int x;\n    x = 1;\n    cout x;\n    return x;\n}\n",
+    },
+    ErrorCode {
+        code: LOOP_NEVER_RUNS,
+        severity: Severity::Warning,
+        title: Text {
+            es: "ciclo que nunca se ejecuta",
+            en: "loop that never runs",
+        },
+        message: Text {
+            es: "la condición del ciclo siempre es falsa; su cuerpo nunca se ejecuta",
+            en: "the loop condition is always false; its body never runs",
+        },
+        explanation: Text {
+            es: "La condición del `while` solo tiene constantes y vale falso, así que el cuerpo \
+no se ejecuta ni una vez. Suele ser una condición invertida o una variable que se quería usar.",
+            en: "The `while` condition has only constants and is false, so the body does not \
+run even once. It is usually an inverted condition or a variable that was meant to be used.",
+        },
+        example: "main() {\n    int i;\n    i = 0;\n    while 1 > 10 {\n        i++;\n    }\n    cout i;\n}\n",
+        fix: "main() {\n    int i;\n    i = 0;\n    while i < 10 {\n        i++;\n    }\n    cout i;\n}\n",
+    },
+    ErrorCode {
+        code: MISSING_MAIN,
+        severity: Severity::Error,
+        title: Text {
+            es: "falta la función main",
+            en: "missing main function",
+        },
+        message: Text {
+            es: "el programa no tiene una función main",
+            en: "the program has no main function",
+        },
+        explanation: Text {
+            es: "La ejecución empieza en `main()`, así que todo programa debe tener una. Fuera \
+de ella solo pueden ir declaraciones de variables globales.",
+            en: "Execution starts at `main()`, so every program must have one. Outside of it \
+there can only be declarations of global variables.",
+        },
+        example: "int x;\n",
+        fix: "int x;\n\nmain() {\n    x = 1;\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: DUPLICATE_MAIN,
+        severity: Severity::Error,
+        title: Text {
+            es: "función main repetida",
+            en: "duplicate main function",
+        },
+        message: Text {
+            es: "ya hay una función main en la línea {line}",
+            en: "there is already a main function on line {line}",
+        },
+        explanation: Text {
+            es: "Un programa tiene un solo punto de entrada. Junte las sentencias de las dos \
+funciones en una sola `main()`.",
+            en: "A program has a single entry point. Merge the statements of both functions \
+into a single `main()`.",
+        },
+        example: "main() {\n    cout 1;\n}\n\nmain() {\n    cout 2;\n}\n",
+        fix: "main() {\n    cout 1;\n    cout 2;\n}\n",
+    },
+    ErrorCode {
+        code: STATEMENT_OUTSIDE_MAIN,
+        severity: Severity::Error,
+        title: Text {
+            es: "sentencia fuera de main",
+            en: "statement outside main",
+        },
+        message: Text {
+            es: "las sentencias ejecutables deben estar dentro de main",
+            en: "executable statements must be inside main",
+        },
+        explanation: Text {
+            es: "En el nivel superior del programa solo pueden ir declaraciones de variables \
+globales y la función `main()`. Asignaciones, ciclos, `cout` y las demás sentencias van dentro \
+de main.",
+            en: "The top level of the program can only hold declarations of global variables \
+and the `main()` function. Assignments, loops, `cout` and the other statements go inside main.",
+        },
+        example: "int x;\nx = 1;\n\nmain() {\n    cout x;\n}\n",
+        fix: "int x;\n\nmain() {\n    x = 1;\n    cout x;\n}\n",
+    },
+    ErrorCode {
+        code: NESTED_MAIN,
+        severity: Severity::Error,
+        title: Text {
+            es: "main dentro de un bloque",
+            en: "main inside a block",
+        },
+        message: Text {
+            es: "la función main solo puede declararse en el nivel superior",
+            en: "the main function can only be declared at the top level",
+        },
+        explanation: Text {
+            es: "`main()` es una función, no una sentencia: no puede aparecer dentro de otra \
+función, de un `if` o de un ciclo. Probablemente falta una '}' antes.",
+            en: "`main()` is a function, not a statement: it cannot appear inside another \
+function, an `if` or a loop. A '}' is probably missing before it.",
+        },
+        example: "main() {\n    cout 1;\n    main() {\n        cout 2;\n    }\n}\n",
+        fix: "main() {\n    cout 1;\n    cout 2;\n}\n",
+    },
+    ErrorCode {
+        code: SHADOWED_VARIABLE,
+        severity: Severity::Warning,
+        title: Text {
+            es: "variable que oculta a otra",
+            en: "variable shadows another",
+        },
+        message: Text {
+            es: "la variable '{name}' oculta a la declarada en la línea {line}",
+            en: "variable '{name}' shadows the one declared on line {line}",
+        },
+        explanation: Text {
+            es: "Cada función, ciclo y rama de un `if` tiene su propio ámbito. Una variable \
+declarada dentro con el mismo nombre que una de afuera es otra variable: mientras dura el \
+bloque, el nombre se refiere a la de adentro y la de afuera no cambia. Esta advertencia se puede \
+desactivar en la configuración.",
+            en: "Each function, loop and branch of an `if` has its own scope. A variable \
+declared inside with the same name as an outer one is a different variable: while the block \
+lasts, the name refers to the inner one and the outer one does not change. This warning can be \
+turned off in the settings.",
+        },
+        example: "main() {\n    int total, i;\n    total = 0;\n    cin i;\n    while i > 0 {\n        int total;\n        total = i;\n        cout total;\n        i--;\n    }\n    cout total;\n}\n",
+        fix: "main() {\n    int total, i;\n    total = 0;\n    cin i;\n    while i > 0 {\n        total = total + i;\n        i--;\n    }\n    cout total;\n}\n",
+    },
+];
+
+pub fn explain(code: &str) -> Option<&'static ErrorCode> {
+    CATALOG.iter().find(|entry| entry.code.eq_ignore_ascii_case(code))
+}
+
+pub fn all() -> &'static [ErrorCode] {
+    &CATALOG
+}
+
+// Un código que no está en el catálogo se trata como error
+pub fn severity(code: &str) -> Severity {
+    explain(code).map_or(Severity::Error, |entry| entry.severity)
+}
+
+// Mensaje corto de `code` en el idioma pedido
+pub fn message(code: &str, locale: Locale, args: &[(&str, &str)]) -> String {
+    match explain(code) {
+        Some(entry) => entry.message.fill(locale, args),
+        None => code.to_string(),
+    }
+}
+
+impl ErrorCode {
+    pub fn localize(&self, locale: Locale) -> Explanation {
+        Explanation {
+            code: self.code,
+            severity: self.severity,
+            title: self.title.get(locale),
+            explanation: self.explanation.get(locale),
+            example: self.example,
+            fix: self.fix,
+        }
+    }
+
+    // Explicación completa como texto, al estilo de `rustc --explain`
+    pub fn render(&self, locale: Locale) -> String {
+        format!(
+            "{}: {}\n\n{}\n\n{}\n\n{}\n\n{}\n\n{}\n",
+            self.code,
+            self.title.get(locale),
+            self.explanation.get(locale),
+            EXAMPLE.get(locale),
+            indent(self.example),
+            FIX.get(locale),
+            indent(self.fix)
+        )
+    }
+}
+
+fn indent(code: &str) -> String {
+    code.lines().map(|line| format!("    {}", line)).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Nombres de los marcadores {nombre} de una plantilla
+    fn placeholders(template: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = template.split('{').skip(1).filter_map(|part| part.split('}').next()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn every_code_is_translated() {
+        for entry in all() {
+            for locale in [Locale::Es, Locale::En] {
+                assert!(!entry.title.get(locale).trim().is_empty(), "{} sin título ({:?})", entry.code, locale);
+                assert!(!entry.message.get(locale).trim().is_empty(), "{} sin mensaje ({:?})", entry.code, locale);
+                assert!(!entry.explanation.get(locale).trim().is_empty(), "{} sin explicación ({:?})", entry.code, locale);
+            }
+            assert_ne!(entry.explanation.es, entry.explanation.en, "{} sin traducir", entry.code);
+            assert_eq!(placeholders(entry.message.es), placeholders(entry.message.en), "{}", entry.code);
+        }
+    }
+
+    #[test]
+    fn codes_are_unique_and_found_by_explain() {
+        for (i, entry) in all().iter().enumerate() {
+            assert!(all()[..i].iter().all(|other| other.code != entry.code), "{} repetido", entry.code);
+            assert_eq!(explain(&entry.code.to_lowercase()).map(|found| found.code), Some(entry.code));
+        }
+    }
+}
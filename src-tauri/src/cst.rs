@@ -0,0 +1,402 @@
+use std::rc::Rc;
+use std::sync::Arc;
+use serde::Serialize;
+use crate::globals::TokenType;
+use crate::globals::NodeType;
+use crate::globals::Token;
+use crate::parse::TreeNode;
+
+// Árbol de sintaxis concreto sin pérdida, al estilo de rowan. El árbol "verde" es inmutable,
+// guarda solo tipos y texto (sin posiciones) y se puede compartir; el árbol "rojo" se
+// construye al recorrerlo y agrega la posición absoluta de cada elemento.
+// Incluye todos los tokens y la trivia (espacios, comentarios y texto con errores léxicos),
+// así que el texto del árbol es exactamente el texto del programa.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Token(TokenType),
+    Whitespace,
+    Comment,
+    // Texto que el escáner reportó como error léxico
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenToken {
+    pub kind: TokenKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GreenElement {
+    Node(Arc<GreenNode>),
+    Token(Arc<GreenToken>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreenNode {
+    pub kind: NodeType,
+    pub width: usize,
+    pub children: Vec<GreenElement>,
+}
+
+impl TokenKind {
+    pub fn is_trivia(&self) -> bool {
+        !matches!(self, TokenKind::Token(_))
+    }
+}
+
+impl GreenElement {
+    pub fn width(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.width,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+}
+
+impl GreenNode {
+    pub fn new(kind: NodeType, children: Vec<GreenElement>) -> Self {
+        let width = children.iter().map(GreenElement::width).sum();
+        GreenNode { kind, width, children }
+    }
+
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.width);
+        self.write_text(&mut text);
+        text
+    }
+
+    fn write_text(&self, text: &mut String) {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => node.write_text(text),
+                GreenElement::Token(token) => text.push_str(&token.text),
+            }
+        }
+    }
+}
+
+// Construye el árbol verde de arriba hacia abajo. Un checkpoint permite abrir después un
+// nodo que envuelve elementos ya agregados (p. ej. el operando izquierdo de una expresión).
+#[derive(Default)]
+pub struct GreenBuilder {
+    parents: Vec<(NodeType, usize)>,
+    children: Vec<GreenElement>,
+}
+
+impl GreenBuilder {
+    pub fn start_node(&mut self, kind: NodeType) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    pub fn checkpoint(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn start_node_at(&mut self, checkpoint: usize, kind: NodeType) {
+        self.parents.push((kind, checkpoint));
+    }
+
+    pub fn finish_node(&mut self) {
+        let (kind, first_child) = self.parents.pop().expect("finish_node sin start_node");
+        let children = self.children.split_off(first_child);
+        self.children.push(GreenElement::Node(Arc::new(GreenNode::new(kind, children))));
+    }
+
+    pub fn token(&mut self, kind: TokenKind, text: &str) {
+        self.children.push(GreenElement::Token(Arc::new(GreenToken { kind, text: text.to_string() })));
+    }
+
+    pub fn finish(mut self) -> Arc<GreenNode> {
+        while !self.parents.is_empty() {
+            self.finish_node();
+        }
+        match self.children.pop() {
+            Some(GreenElement::Node(root)) if self.children.is_empty() => root,
+            Some(last) => {
+                self.children.push(last);
+                Arc::new(GreenNode::new(NodeType::MainRoot, self.children))
+            }
+            None => Arc::new(GreenNode::new(NodeType::MainRoot, Vec::new())),
+        }
+    }
+}
+
+// Nodo del árbol rojo: un nodo verde con su desplazamiento (en bytes) en el texto
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+#[derive(Debug)]
+struct NodeData {
+    green: Arc<GreenNode>,
+    offset: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    green: Arc<GreenToken>,
+    offset: usize,
+}
+
+// CST serializable para herramientas externas: tipo, rango en bytes y el texto de los tokens
+#[derive(Debug, Clone, Serialize)]
+pub struct ConcreteNode {
+    pub kind: String,
+    pub start: usize,
+    pub end: usize,
+    pub text: Option<String>,
+    pub children: Vec<ConcreteNode>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Arc<GreenNode>) -> Self {
+        SyntaxNode(Rc::new(NodeData { green, offset: 0 }))
+    }
+
+    pub fn kind(&self) -> NodeType {
+        self.0.green.kind.clone()
+    }
+
+    // Rango [inicio, fin) del nodo en bytes del texto
+    pub fn range(&self) -> (usize, usize) {
+        (self.0.offset, self.0.offset + self.0.green.width)
+    }
+
+    pub fn text(&self) -> String {
+        self.0.green.text()
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::with_capacity(self.0.green.children.len());
+        for child in &self.0.green.children {
+            children.push(match child {
+                GreenElement::Node(green) => SyntaxElement::Node(SyntaxNode(Rc::new(NodeData { green: green.clone(), offset }))),
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken { green: green.clone(), offset }),
+            });
+            offset += child.width();
+        }
+        children
+    }
+
+    pub fn children(&self) -> Vec<SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    // Tokens propios del nodo (no los de sus hijos) sin la trivia
+    pub fn significant_tokens(&self) -> Vec<SyntaxToken> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Token(token) if !token.kind().is_trivia() => Some(token),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> &TokenKind {
+        &self.green.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    pub fn range(&self) -> (usize, usize) {
+        (self.offset, self.offset + self.green.text.len())
+    }
+}
+
+// Vista del CST como el TreeNode de siempre: solo los nodos, sin tokens ni trivia. Un Factor
+// sin nodos hijos es una hoja con su token (identificador, número u operador).
+pub fn tree_view(node: &SyntaxNode) -> TreeNode {
+    let children = node.children();
+    if node.kind() == NodeType::Factor && children.is_empty() {
+        if let Some(token) = node.significant_tokens().first() {
+            if let TokenKind::Token(token_type) = token.kind() {
+                return TreeNode::leaf(token_type.clone(), token.text());
+            }
+        }
+    }
+    let mut tree = TreeNode::new(node.kind());
+    tree.children = children.iter().map(tree_view).collect();
+    tree
+}
+
+pub fn dump(node: &SyntaxNode) -> ConcreteNode {
+    let (start, end) = node.range();
+    let children = node
+        .children_with_tokens()
+        .into_iter()
+        .map(|child| match child {
+            SyntaxElement::Node(node) => dump(&node),
+            SyntaxElement::Token(token) => {
+                let (start, end) = token.range();
+                let kind = match token.kind() {
+                    TokenKind::Token(token_type) => format!("{:?}", token_type),
+                    trivia => format!("{:?}", trivia),
+                };
+                ConcreteNode { kind, start, end, text: Some(token.text().to_string()), children: Vec::new() }
+            }
+        })
+        .collect();
+    ConcreteNode { kind: format!("{:?}", node.kind()), start, end, text: None, children }
+}
+
+// Trivia que precede a cada token: el texto entre el token anterior y él. La del token
+// ENDFILE es la trivia final del archivo. Los tokens se piden en orden, a medida que el
+// parser los consume, así que no hace falta tener la lista completa.
+pub struct Trivia<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+    // Fin del último token en el texto
+    cursor: usize,
+}
+
+impl<'a> Trivia<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        Trivia { source, line_starts, cursor: 0 }
+    }
+
+    fn offset_of(&self, line: usize, column: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let rest = &self.source[start..];
+        match rest.char_indices().nth(column.checked_sub(1)?) {
+            Some((i, _)) => Some(start + i),
+            None => Some(self.source.len()),
+        }
+    }
+
+    // Trivia entre el token anterior y `token`; vacía si el token no está en el texto
+    pub fn before(&mut self, token: &Token) -> Vec<GreenToken> {
+        let (token_type, lexeme, line, column) = token;
+        let start = match token_type {
+            TokenType::ENDFILE => Some(self.source.len()),
+            _ => self.offset_of(*line, *column).filter(|start| self.source[*start..].starts_with(lexeme.as_str())),
+        };
+        match start {
+            Some(start) if start >= self.cursor => {
+                let trivia = split_trivia(&self.source[self.cursor..start]);
+                self.cursor = start + if *token_type == TokenType::ENDFILE { 0 } else { lexeme.len() };
+                trivia
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Texto del token tal como aparece en el programa (ENDFILE no ocupa texto)
+pub fn token_text(token: &Token) -> &str {
+    match token.0 {
+        TokenType::ENDFILE => "",
+        _ => &token.1,
+    }
+}
+
+// Divide el texto entre dos tokens en espacios, comentarios y texto con errores léxicos
+fn split_trivia(gap: &str) -> Vec<GreenToken> {
+    let mut pieces = Vec::new();
+    let mut rest = gap;
+    while let Some(c) = rest.chars().next() {
+        let (kind, length) = if c.is_whitespace() {
+            (TokenKind::Whitespace, rest.find(|c: char| !c.is_whitespace()).unwrap_or(rest.len()))
+        } else if rest.starts_with("//") {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if rest.starts_with("/*") {
+            (TokenKind::Comment, block_comment_length(rest))
+        } else {
+            let end = rest
+                .char_indices()
+                .find(|(i, c)| c.is_whitespace() || rest[*i..].starts_with("//") || rest[*i..].starts_with("/*"))
+                .map(|(i, _)| i)
+                .unwrap_or(rest.len());
+            (TokenKind::Unknown, end)
+        };
+        pieces.push(GreenToken { kind, text: rest[..length].to_string() });
+        rest = &rest[length..];
+    }
+    pieces
+}
+
+// Longitud de un comentario múltiple que empieza en `text`, con anidamiento como en el
+// escáner; si no se cierra llega hasta el final
+fn block_comment_length(text: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with("/*") {
+            depth += 1;
+            i += 2;
+        } else if text[i..].starts_with("*/") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::ENGLISH;
+    use crate::locale::Locale;
+    use crate::parse::parse_source;
+
+    // El árbol concreto debe reproducir el texto exacto, incluso con errores
+    fn round_trip(source: &str) {
+        let (result, _) = parse_source(source, &ENGLISH, Locale::Es);
+        let root = SyntaxNode::new_root(result.green);
+        assert_eq!(root.text(), source);
+    }
+
+    #[test]
+    fn keeps_comments() {
+        round_trip("// inicio\nmain() { // abre\n    int x; /* declara */\n    x = 1; // asigna\n}\n// fin");
+    }
+
+    #[test]
+    fn keeps_block_comments() {
+        round_trip("main() {\n    /* externo /* interno */ x = 1; */\n    int y;\n}\n");
+        round_trip("main() {\n    int x;\n    /* sin cerrar\n    x = 1;\n}\n");
+        round_trip("/*");
+    }
+
+    #[test]
+    fn keeps_lexical_errors() {
+        round_trip("main() {\n    int x;\n    x = 1 @ 2 $;\n    y = 3.;\n}\n");
+        round_trip("main() { x = 1.; & | @ }");
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        round_trip("main() {\r\n    int x;\r\n    x = 1; // fin\r\n}\r\n");
+    }
+
+    #[test]
+    fn keeps_trailing_whitespace() {
+        round_trip("main() {   \n    int x;\t\n}\n\n   \t");
+        round_trip("   ");
+        round_trip("");
+    }
+}
@@ -0,0 +1,332 @@
+use serde::Serialize;
+use crate::globals::{StateType, Token, TokenType};
+use crate::dialect::Dialect;
+use crate::scan::Scanner;
+
+// Clases de caracteres sobre las que se definen las transiciones del DFA
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum CharClass {
+    Letter,     // letras ASCII y '_'
+    Digit,
+    Dot,
+    Equal,
+    Less,
+    Greater,
+    Bang,
+    Plus,
+    Minus,
+    Slash,
+    Star,
+    Symbol,     // símbolos de un solo carácter: % ^ ( ) { } , ; & | :
+    Newline,
+    Whitespace,
+    Other,      // cualquier carácter que no pertenece al lenguaje
+    Eof,
+    Any,        // cualquier clase sin una transición propia en el estado
+}
+
+// Qué hace el escáner al tomar una transición
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum Action {
+    Move,                // solo cambia de estado
+    Save,                // el carácter forma parte del lexema
+    Skip,                // el carácter se descarta (espacios, cuerpo de comentarios)
+    Discard,             // se descarta también el lexema leído hasta ahora ('//')
+    OpenComment,         // inicia un comentario múltiple ('/*')
+    NestComment,         // comentario anidado dentro de otro
+    CloseComment,        // cierra un nivel; vuelve a InMultiComment si quedan niveles abiertos
+    Unterminated,        // fin de archivo dentro de un comentario múltiple
+    Error,               // el lexema se reporta como error léxico
+    Accept(Emit),        // el lexema forma un token
+}
+
+// Tipo de token que produce una transición de aceptación
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum Emit {
+    Token(TokenType),
+    Keyword,             // ID o palabra reservada según el dialecto
+    Symbol,              // según el carácter leído
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transition {
+    pub from: StateType,
+    pub on: &'static [CharClass],
+    pub to: StateType,
+    pub consume: bool,   // si es falso el carácter se deja para el siguiente estado
+    pub action: Action,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Dfa {
+    pub states: Vec<StateType>,
+    pub classes: Vec<CharClass>,
+    pub start: StateType,
+    pub accepting: Vec<StateType>,
+    pub transitions: Vec<Transition>,
+}
+
+// Estados visitados por el escáner para producir un token
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenTrace {
+    pub token: Token,
+    pub states: Vec<StateType>,
+}
+
+pub const STATES: [StateType; 18] = [
+    StateType::Start,
+    StateType::InAssign,
+    StateType::InLess,
+    StateType::InGreater,
+    StateType::InNot,
+    StateType::InPlus,
+    StateType::InMinus,
+    StateType::InSlash,
+    StateType::InComment,
+    StateType::InMultiComment,
+    StateType::InMultiCommentStar,
+    StateType::InMultiCommentSlash,
+    StateType::InNum,
+    StateType::InPoint,
+    StateType::InReal,
+    StateType::InId,
+    StateType::Done,
+    StateType::EndFile,
+];
+
+pub const CLASSES: [CharClass; 17] = [
+    CharClass::Letter,
+    CharClass::Digit,
+    CharClass::Dot,
+    CharClass::Equal,
+    CharClass::Less,
+    CharClass::Greater,
+    CharClass::Bang,
+    CharClass::Plus,
+    CharClass::Minus,
+    CharClass::Slash,
+    CharClass::Star,
+    CharClass::Symbol,
+    CharClass::Newline,
+    CharClass::Whitespace,
+    CharClass::Other,
+    CharClass::Eof,
+    CharClass::Any,
+];
+
+const fn t(from: StateType, on: &'static [CharClass], to: StateType, consume: bool, action: Action) -> Transition {
+    Transition { from, on, to, consume, action }
+}
+
+use StateType::*;
+use CharClass::*;
+
+// Tabla de transiciones del escáner. Dentro de cada estado, las clases concretas tienen
+// prioridad sobre `Any`.
+pub static TRANSITIONS: [Transition; 55] = [
+    t(Start, &[Whitespace, Newline], Start, true, Action::Skip),
+    t(Start, &[Letter], InId, true, Action::Save),
+    t(Start, &[Digit], InNum, true, Action::Save),
+    t(Start, &[Slash], InSlash, true, Action::Save),
+    t(Start, &[Equal], InAssign, true, Action::Save),
+    t(Start, &[Less], InLess, true, Action::Save),
+    t(Start, &[Greater], InGreater, true, Action::Save),
+    t(Start, &[Bang], InNot, true, Action::Save),
+    t(Start, &[Plus], InPlus, true, Action::Save),
+    t(Start, &[Minus], InMinus, true, Action::Save),
+    t(Start, &[Star], Done, true, Action::Accept(Emit::Token(TokenType::TIMES))),
+    t(Start, &[Symbol], Done, true, Action::Accept(Emit::Symbol)),
+    t(Start, &[Eof], EndFile, false, Action::Move),
+    t(Start, &[Any], Start, true, Action::Error),
+
+    t(InId, &[Letter, Digit], InId, true, Action::Save),
+    t(InId, &[Any], Done, false, Action::Accept(Emit::Keyword)),
+
+    t(InNum, &[Digit], InNum, true, Action::Save),
+    t(InNum, &[Dot], InPoint, true, Action::Save),
+    t(InNum, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::NumInt))),
+    t(InPoint, &[Digit], InReal, true, Action::Save),
+    t(InPoint, &[Any], Start, false, Action::Error),
+    t(InReal, &[Digit], InReal, true, Action::Save),
+    t(InReal, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::NumReal))),
+
+    t(InAssign, &[Equal], Done, true, Action::Accept(Emit::Token(TokenType::EQ))),
+    t(InAssign, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::ASSIGN))),
+    t(InLess, &[Equal], Done, true, Action::Accept(Emit::Token(TokenType::LTE))),
+    t(InLess, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::LT))),
+    t(InGreater, &[Equal], Done, true, Action::Accept(Emit::Token(TokenType::GTE))),
+    t(InGreater, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::GT))),
+    t(InNot, &[Equal], Done, true, Action::Accept(Emit::Token(TokenType::NEQ))),
+    t(InNot, &[Any], Start, false, Action::Error),
+    t(InPlus, &[Plus], Done, true, Action::Accept(Emit::Token(TokenType::INCREMENT))),
+    t(InPlus, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::PLUS))),
+    t(InMinus, &[Minus], Done, true, Action::Accept(Emit::Token(TokenType::DECREMENT))),
+    t(InMinus, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::MINUS))),
+
+    t(InSlash, &[Slash], InComment, true, Action::Discard),
+    t(InSlash, &[Star], InMultiComment, true, Action::OpenComment),
+    t(InSlash, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::DIVIDE))),
+    t(InComment, &[Newline], Start, true, Action::Skip),
+    t(InComment, &[Eof], Start, false, Action::Move),
+    t(InComment, &[Any], InComment, true, Action::Skip),
+
+    t(InMultiComment, &[Star], InMultiCommentStar, true, Action::Skip),
+    t(InMultiComment, &[Slash], InMultiCommentSlash, true, Action::Skip),
+    t(InMultiComment, &[Eof], EndFile, false, Action::Unterminated),
+    t(InMultiComment, &[Any], InMultiComment, true, Action::Skip),
+    t(InMultiCommentStar, &[Slash], Start, true, Action::CloseComment),
+    t(InMultiCommentStar, &[Star], InMultiCommentStar, true, Action::Skip),
+    t(InMultiCommentStar, &[Eof], EndFile, false, Action::Unterminated),
+    t(InMultiCommentStar, &[Any], InMultiComment, true, Action::Skip),
+    t(InMultiCommentSlash, &[Star], InMultiComment, true, Action::NestComment),
+    t(InMultiCommentSlash, &[Slash], InMultiCommentSlash, true, Action::Skip),
+    t(InMultiCommentSlash, &[Eof], EndFile, false, Action::Unterminated),
+    t(InMultiCommentSlash, &[Any], InMultiComment, true, Action::Skip),
+
+    t(EndFile, &[Any], Done, false, Action::Accept(Emit::Token(TokenType::ENDFILE))),
+
+    // Done es el estado de aceptación: el escáner devuelve el token y reinicia en Start
+    t(Done, &[Any], Start, false, Action::Move),
+];
+
+lazy_static! {
+    // Índice denso (estado, clase) -> transición, para no recorrer la tabla en cada carácter
+    static ref INDEX: Vec<Vec<usize>> = {
+        let mut index = vec![vec![usize::MAX; CLASSES.len()]; STATES.len()];
+        for (i, transition) in TRANSITIONS.iter().enumerate() {
+            for class in transition.on.iter().filter(|class| **class != Any) {
+                index[transition.from as usize][*class as usize] = i;
+            }
+        }
+        for (i, transition) in TRANSITIONS.iter().enumerate() {
+            if transition.on.contains(&Any) {
+                for slot in index[transition.from as usize].iter_mut() {
+                    if *slot == usize::MAX {
+                        *slot = i;
+                    }
+                }
+            }
+        }
+        index
+    };
+}
+
+impl CharClass {
+    pub fn of(c: Option<char>) -> CharClass {
+        match c {
+            None => Eof,
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => Letter,
+            Some(c) if c.is_ascii_digit() => Digit,
+            Some('.') => Dot,
+            Some('=') => Equal,
+            Some('<') => Less,
+            Some('>') => Greater,
+            Some('!') => Bang,
+            Some('+') => Plus,
+            Some('-') => Minus,
+            Some('/') => Slash,
+            Some('*') => Star,
+            Some(c) if symbol_token(c).is_some() => Symbol,
+            Some('\n') => Newline,
+            Some(c) if c.is_whitespace() => Whitespace,
+            Some(_) => Other,
+        }
+    }
+}
+
+// Token de los símbolos de un solo carácter
+pub fn symbol_token(c: char) -> Option<TokenType> {
+    match c {
+        '%' => Some(TokenType::MODULO),
+        '^' => Some(TokenType::POWER),
+        '(' => Some(TokenType::LPAREN),
+        ')' => Some(TokenType::RPAREN),
+        '{' => Some(TokenType::LBRACE),
+        '}' => Some(TokenType::RBRACE),
+        ',' => Some(TokenType::COMMA),
+        ';' => Some(TokenType::SEMICOLON),
+        '&' => Some(TokenType::AND),
+        '|' => Some(TokenType::OR),
+        ':' => Some(TokenType::COLON),
+        _ => None,
+    }
+}
+
+// Transición que toma el DFA desde `state` al leer un carácter de la clase `class`
+pub fn transition(state: StateType, class: CharClass) -> &'static Transition {
+    &TRANSITIONS[INDEX[state as usize][class as usize]]
+}
+
+pub fn describe() -> Dfa {
+    Dfa {
+        states: STATES.to_vec(),
+        classes: CLASSES.to_vec(),
+        start: Start,
+        accepting: vec![Done],
+        transitions: TRANSITIONS.to_vec(),
+    }
+}
+
+fn class_label(class: &CharClass) -> &'static str {
+    match class {
+        Letter => "letra",
+        Digit => "dígito",
+        Dot => ".",
+        Equal => "=",
+        Less => "<",
+        Greater => ">",
+        Bang => "!",
+        Plus => "+",
+        Minus => "-",
+        Slash => "/",
+        Star => "*",
+        Symbol => "símbolo",
+        Newline => "\\\\n",
+        Whitespace => "espacio",
+        Other => "otro",
+        Eof => "EOF",
+        Any => "otro",
+    }
+}
+
+fn action_label(action: &Action) -> String {
+    match action {
+        Action::Accept(Emit::Token(token)) => format!("{:?}", token),
+        Action::Accept(Emit::Keyword) => "ID/reservada".to_string(),
+        Action::Accept(Emit::Symbol) => "símbolo".to_string(),
+        Action::Move | Action::Save | Action::Skip => String::new(),
+        other => format!("{:?}", other),
+    }
+}
+
+// Exporta el DFA en formato Graphviz DOT; las transiciones que no consumen el carácter van punteadas
+pub fn to_dot() -> String {
+    let mut dot = String::from("digraph scanner {\n    rankdir=LR;\n    node [shape=circle];\n    Done [shape=doublecircle];\n    __start [shape=point];\n    __start -> Start;\n");
+    for transition in TRANSITIONS.iter() {
+        let classes: Vec<&str> = transition.on.iter().map(class_label).collect();
+        let action = action_label(&transition.action);
+        let label = if action.is_empty() {
+            classes.join(", ")
+        } else {
+            format!("{} / {}", classes.join(", "), action)
+        };
+        let style = if transition.consume { "" } else { ", style=dashed" };
+        dot.push_str(&format!("    {:?} -> {:?} [label=\"{}\"{}];\n", transition.from, transition.to, label, style));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub fn to_json() -> Result<String, String> {
+    serde_json::to_string_pretty(&describe()).map_err(|e| e.to_string())
+}
+
+// Recorre la entrada y devuelve, para cada token, la secuencia de estados visitados
+pub fn trace(content: &str, dialect: &Dialect) -> Vec<TokenTrace> {
+    let mut scanner = Scanner::new(content).with_dialect(dialect).with_trace();
+    let mut traces = Vec::new();
+    while let Some(token) = scanner.next() {
+        traces.push(TokenTrace { token, states: scanner.take_trace() });
+    }
+    traces
+}
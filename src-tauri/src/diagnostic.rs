@@ -0,0 +1,300 @@
+use serde::{Serialize, Deserialize};
+use crate::globals::TokenType;
+use crate::globals::Token;
+use crate::dialect::Dialect;
+use crate::codes;
+use crate::locale::{Locale, Text};
+use crate::ast::Span;
+
+// Error con posición en el código fuente. `code` identifica el error en el catálogo
+// (codes.rs) y `snippet` trae la línea del error con un '^' bajo el token señalado
+// cuando se conoce el texto del programa.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub code: String,
+    pub severity: Severity,
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub expected: Vec<String>,
+    pub found: Option<String>,
+    pub snippet: Option<String>,
+    // Otras posiciones que explican el diagnóstico, como la declaración que otra oculta
+    #[serde(default)]
+    pub related: Vec<Related>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Related {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub snippet: Option<String>,
+}
+
+// Los errores impiden compilar el programa; las advertencias solo señalan código sospechoso
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+const LEXICAL_ERROR: Text = Text {
+    es: "Error léxico en {position}: {message}",
+    en: "Lexical error at {position}: {message}",
+};
+
+const SYNTAX_ERROR: Text = Text {
+    es: "Error de sintaxis en {position}: {message}",
+    en: "Syntax error at {position}: {message}",
+};
+
+const SEMANTIC_ERROR: Text = Text {
+    es: "Error semántico en {position}: {message}",
+    en: "Semantic error at {position}: {message}",
+};
+
+const SEMANTIC_WARNING: Text = Text {
+    es: "Advertencia en {position}: {message}",
+    en: "Warning at {position}: {message}",
+};
+
+pub const AFTER: Text = Text {
+    es: " después de '{lexeme}'",
+    en: " after '{lexeme}'",
+};
+
+pub const A_STATEMENT: Text = Text {
+    es: "una sentencia",
+    en: "a statement",
+};
+
+pub const AN_EXPRESSION: Text = Text {
+    es: "una expresión",
+    en: "an expression",
+};
+
+pub const AN_OPERATOR: Text = Text {
+    es: "un operador",
+    en: "an operator",
+};
+
+const AN_IDENTIFIER: Text = Text {
+    es: "un identificador",
+    en: "an identifier",
+};
+
+const AN_INTEGER: Text = Text {
+    es: "un número entero",
+    en: "an integer number",
+};
+
+const A_REAL: Text = Text {
+    es: "un número real",
+    en: "a real number",
+};
+
+const END_OF_FILE: Text = Text {
+    es: "el fin de archivo",
+    en: "the end of file",
+};
+
+const A_LEXICAL_ERROR: Text = Text {
+    es: "un error léxico",
+    en: "a lexical error",
+};
+
+const OR: Text = Text {
+    es: "o",
+    en: "or",
+};
+
+impl Diagnostic {
+    // Diagnóstico con el mensaje del catálogo para `code`, precedido por la fase y la posición
+    pub fn new(code: &str, locale: Locale, token: Option<&Token>, args: &[(&str, &str)]) -> Self {
+        let (line, column, length) = match token {
+            Some((TokenType::ENDFILE, _, line, column)) => (*line, *column, 1),
+            Some((_, lexeme, line, column)) => (*line, *column, lexeme.chars().count().max(1)),
+            None => (0, 0, 1),
+        };
+        Diagnostic::at(code, locale, Span { line, column }, length, args)
+    }
+
+    // Diagnóstico en una posición del árbol tipado; lo usan las fases posteriores al parser
+    pub fn at(code: &str, locale: Locale, span: Span, length: usize, args: &[(&str, &str)]) -> Self {
+        let severity = codes::severity(code);
+        let phase = match (code.chars().next(), severity) {
+            (Some('L'), _) => LEXICAL_ERROR,
+            (Some('S'), Severity::Warning) => SEMANTIC_WARNING,
+            (Some('S'), Severity::Error) => SEMANTIC_ERROR,
+            _ => SYNTAX_ERROR,
+        };
+        let position = format!("{}:{}", span.line, span.column);
+        let message = codes::message(code, locale, args);
+        Diagnostic {
+            code: code.to_string(),
+            severity,
+            message: phase.fill(locale, &[("position", &position), ("message", &message)]),
+            line: span.line,
+            column: span.column,
+            length: length.max(1),
+            expected: Vec::new(),
+            found: None,
+            snippet: None,
+            related: Vec::new(),
+        }
+    }
+
+    pub fn with_related(mut self, message: String, span: Span, length: usize) -> Self {
+        self.related.push(Related { message, line: span.line, column: span.column, length: length.max(1), snippet: None });
+        self
+    }
+
+    pub fn with_snippet(mut self, source: &str) -> Self {
+        self.snippet = snippet(source, self.line, self.column, self.length);
+        for related in &mut self.related {
+            related.snippet = snippet(source, related.line, related.column, related.length);
+        }
+        self
+    }
+
+    // Error léxico reportado por el escáner como token (ERROR o InMultipleComment)
+    pub fn lexical(error: &Token, locale: Locale) -> Self {
+        let code = match error {
+            (TokenType::InMultipleComment, _, _, _) => codes::UNTERMINATED_COMMENT,
+            (_, lexeme, _, _) if lexeme.ends_with('.') => codes::MALFORMED_REAL,
+            (_, lexeme, _, _) if lexeme == "!" => codes::LONE_BANG,
+            _ => codes::UNKNOWN_CHARACTER,
+        };
+        let mut diagnostic = Diagnostic::new(code, locale, Some(error), &[("lexeme", &error.1)]);
+        if code == codes::UNTERMINATED_COMMENT {
+            diagnostic.length = 2;
+        }
+        diagnostic
+    }
+
+    // Código y mensaje seguidos del fragmento de código, como se muestra en la terminal
+    pub fn render(&self) -> String {
+        let mut text = match &self.snippet {
+            Some(snippet) => format!("[{}] {}\n{}", self.code, self.message, snippet),
+            None => format!("[{}] {}", self.code, self.message),
+        };
+        for related in &self.related {
+            text.push_str(&format!("\n  {}:{}: {}", related.line, related.column, related.message));
+            if let Some(snippet) = &related.snippet {
+                text.push('\n');
+                text.push_str(snippet);
+            }
+        }
+        text
+    }
+}
+
+// Línea `line` del código con un '^' de `length` caracteres bajo la columna `column`
+pub fn snippet(source: &str, line: usize, column: usize, length: usize) -> Option<String> {
+    let text = source.split('\n').nth(line.checked_sub(1)?)?.trim_end_matches('\r');
+    let gutter = line.to_string().len();
+    // Se conservan los tabuladores para que el '^' quede alineado con el texto
+    let padding: String = text
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    Some(format!(
+        "{:>width$} | {}\n{:>width$} | {}{}",
+        line,
+        text,
+        "",
+        padding,
+        "^".repeat(length.max(1)),
+        width = gutter
+    ))
+}
+
+// Cómo se escribe un tipo de token en el código fuente, para los mensajes de error
+pub fn spelling(token: &TokenType, dialect: &Dialect, locale: Locale) -> String {
+    if let Some(keyword) = dialect.spelling(token) {
+        return format!("'{}'", keyword);
+    }
+    let symbol = match token {
+        TokenType::ID => return AN_IDENTIFIER.get(locale).to_string(),
+        TokenType::NumInt => return AN_INTEGER.get(locale).to_string(),
+        TokenType::NumReal => return A_REAL.get(locale).to_string(),
+        TokenType::ENDFILE => return END_OF_FILE.get(locale).to_string(),
+        TokenType::ERROR | TokenType::InMultipleComment => return A_LEXICAL_ERROR.get(locale).to_string(),
+        TokenType::PLUS => "+",
+        TokenType::MINUS => "-",
+        TokenType::TIMES => "*",
+        TokenType::DIVIDE => "/",
+        TokenType::MODULO => "%",
+        TokenType::POWER => "^",
+        TokenType::EQ => "==",
+        TokenType::NEQ => "!=",
+        TokenType::LT => "<",
+        TokenType::LTE => "<=",
+        TokenType::GT => ">",
+        TokenType::GTE => ">=",
+        TokenType::AND => "&",
+        TokenType::OR => "|",
+        TokenType::LPAREN => "(",
+        TokenType::RPAREN => ")",
+        TokenType::LBRACE => "{",
+        TokenType::RBRACE => "}",
+        TokenType::COMMA => ",",
+        TokenType::COLON => ":",
+        TokenType::SEMICOLON => ";",
+        TokenType::ASSIGN => "=",
+        TokenType::INCREMENT => "++",
+        TokenType::DECREMENT => "--",
+        other => return format!("{:?}", other),
+    };
+    format!("'{}'", symbol)
+}
+
+// Token encontrado tal como aparece en el código
+pub fn found(token: Option<&Token>, locale: Locale) -> String {
+    match token {
+        Some((TokenType::ENDFILE, _, _, _)) | None => END_OF_FILE.get(locale).to_string(),
+        Some((_, lexeme, _, _)) => format!("'{}'", lexeme),
+    }
+}
+
+// Une las alternativas como en una frase: "';', '}' o un operador"
+pub fn join_alternatives(alternatives: &[String], locale: Locale) -> String {
+    match alternatives {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} {} {}", rest.join(", "), OR.get(locale), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes;
+    use crate::dialect::ENGLISH;
+    use crate::parse::parse_source;
+
+    fn first_error(source: &str, locale: Locale) -> Diagnostic {
+        let (result, _) = parse_source(source, &ENGLISH, locale);
+        result.errors[0].clone().with_snippet(source)
+    }
+
+    #[test]
+    fn missing_semicolon_lists_the_expected_tokens() {
+        let source = "main() {\n    int x;\n    x = 1\n    cout x;\n}\n";
+        let error = first_error(source, Locale::Es);
+        assert_eq!(error.code, codes::MISSING_SEMICOLON);
+        assert_eq!((error.line, error.column, error.length), (4, 5, 4));
+        assert_eq!(error.expected, ["';'", "un operador"]);
+        assert_eq!(error.found.as_deref(), Some("cout"));
+        assert_eq!(error.snippet.as_deref(), Some("4 |     cout x;\n  |     ^^^^"));
+        assert_eq!(first_error(source, Locale::En).expected, ["';'", "an operator"]);
+        // En una declaración también puede seguir otra variable
+        let error = first_error("main() {\n    int x\n    x = 1;\n}\n", Locale::Es);
+        assert_eq!(error.expected, ["','", "';'"]);
+    }
+}
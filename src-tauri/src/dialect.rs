@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::fs;
+use serde::{Serialize, Deserialize};
+use crate::globals::TokenType;
+
+// Dialectos incluidos en el binario; se seleccionan por nombre
+const BUILTIN_DIALECTS: [(&str, &str); 2] = [
+    ("english", include_str!("../dialects/english.json")),
+    ("spanish", include_str!("../dialects/spanish.json")),
+];
+
+// Tipos de token que una palabra reservada puede producir
+const KEYWORD_TOKENS: [TokenType; 14] = [
+    TokenType::IF,
+    TokenType::ELSE,
+    TokenType::DO,
+    TokenType::WHILE,
+    TokenType::REPEAT,
+    TokenType::UNTIL,
+    TokenType::READ,
+    TokenType::WRITE,
+    TokenType::INTEGER,
+    TokenType::DOUBLE,
+    TokenType::MAIN,
+    TokenType::RETURN,
+    TokenType::CIN,
+    TokenType::COUT,
+];
+
+lazy_static! {
+    pub static ref ENGLISH: Dialect = Dialect::builtin("english").unwrap();
+}
+
+// Tabla de palabras reservadas de un dialecto del lenguaje. Todos los dialectos producen
+// los mismos TokenType, así que el parser funciona igual para cualquiera de ellos.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dialect {
+    pub name: String,
+    pub keywords: HashMap<String, TokenType>,
+}
+
+impl Dialect {
+    pub fn builtin(name: &str) -> Result<Dialect, String> {
+        match BUILTIN_DIALECTS.iter().find(|(builtin, _)| *builtin == name) {
+            Some((_, definition)) => Dialect::from_json(definition),
+            None => Err(format!("Dialecto desconocido: {}", name)),
+        }
+    }
+
+    // Carga un dialecto incluido por su nombre o, si no existe, desde un archivo JSON
+    pub fn load(name_or_path: &str) -> Result<Dialect, String> {
+        if BUILTIN_DIALECTS.iter().any(|(builtin, _)| *builtin == name_or_path) {
+            return Dialect::builtin(name_or_path);
+        }
+        match fs::read_to_string(name_or_path) {
+            Ok(definition) => Dialect::from_json(&definition),
+            Err(e) => Err(format!("No se pudo leer el dialecto '{}': {}", name_or_path, e)),
+        }
+    }
+
+    pub fn from_json(definition: &str) -> Result<Dialect, String> {
+        let dialect: Dialect = serde_json::from_str(definition)
+            .map_err(|e| format!("Definición de dialecto inválida: {}", e))?;
+        dialect.validate()?;
+        Ok(dialect)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (word, token) in &self.keywords {
+            let mut chars = word.chars();
+            let is_identifier = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !is_identifier {
+                return Err(format!("La palabra reservada '{}' no es un identificador válido", word));
+            }
+            if !KEYWORD_TOKENS.contains(token) {
+                return Err(format!("La palabra reservada '{}' no puede producir el token {:?}", word, token));
+            }
+        }
+        Ok(())
+    }
+
+    // Palabra reservada que produce `token` en este dialecto (la primera en orden alfabético si hay varias)
+    pub fn spelling(&self, token: &TokenType) -> Option<&str> {
+        self.keywords
+            .iter()
+            .filter(|(_, keyword_token)| *keyword_token == token)
+            .map(|(word, _)| word.as_str())
+            .min()
+    }
+
+    // Función para buscar palabras reservadas y devolver su TokenType correspondiente
+    pub fn reserved_lookup(&self, s: &str) -> TokenType {
+        self.keywords.get(s).cloned().unwrap_or(TokenType::ID)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scan::get_token;
+
+    fn token_types(source: &str, dialect: &Dialect) -> Vec<TokenType> {
+        let (tokens, errors) = get_token(source, dialect);
+        assert!(errors.is_empty());
+        tokens.into_iter().map(|token| token.0).collect()
+    }
+
+    #[test]
+    fn spanish_keywords_scan_to_the_same_tokens() {
+        let spanish = Dialect::load("spanish").unwrap();
+        let english = Dialect::load("english").unwrap();
+        let es = "principal() {\n    entero x; real y;\n    leer x;\n    si (x > 0) { escribir x; } sino { y = 1.5; }\n    mientras (x > 0) { x--; }\n    hacer { x++; } mientras (x < 3);\n    repetir { x--; } hasta (x == 0);\n    cout x;\n    retornar 0;\n}\n";
+        let en = "main() {\n    int x; double y;\n    read x;\n    if (x > 0) { write x; } else { y = 1.5; }\n    while (x > 0) { x--; }\n    do { x++; } while (x < 3);\n    repeat { x--; } until (x == 0);\n    cout x;\n    return 0;\n}\n";
+        assert_eq!(token_types(es, &spanish), token_types(en, &english));
+        // Las palabras del otro dialecto son identificadores comunes
+        assert_eq!(token_types("if mientras", &spanish), [TokenType::ID, TokenType::WHILE, TokenType::ENDFILE]);
+    }
+
+    #[test]
+    fn malformed_definitions_are_errors() {
+        let path = std::env::temp_dir().join(format!("dialecto-invalido-{}.json", std::process::id()));
+        fs::write(&path, "{ \"name\": \"roto\", \"keywords\": { \"si\": ").unwrap();
+        let result = Dialect::load(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+        assert!(Dialect::load("no-existe.json").is_err());
+        assert!(Dialect::from_json("{ \"name\": \"x\", \"keywords\": { \"si no\": \"IF\" } }").is_err());
+        assert!(Dialect::from_json("{ \"name\": \"x\", \"keywords\": { \"mas\": \"PLUS\" } }").is_err());
+    }
+}
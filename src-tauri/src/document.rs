@@ -0,0 +1,272 @@
+use serde::{Serialize, Deserialize};
+use crate::globals::Token;
+use crate::scan::Scanner;
+use crate::dialect::Dialect;
+
+// Edición de texto enviada por el editor: reemplaza el rango [inicio, fin) por `text`.
+// Las posiciones usan la misma convención que los tokens (línea y columna desde 1).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+    pub text: String,
+}
+
+// Cambio en la lista de tokens tras una edición: se eliminan `deleted` tokens a partir de
+// `start`, se insertan `inserted` en su lugar y los tokens que siguen se mueven según `shift`.
+// Los errores léxicos se devuelven completos.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenDelta {
+    pub start: usize,
+    pub deleted: usize,
+    pub inserted: Vec<Token>,
+    pub shift: Shift,
+    pub errors: Vec<Token>,
+}
+
+// Desplazamiento de los tokens posteriores a una edición: todos bajan `lines` líneas, y los
+// que estaban en `end_line` (la línea donde terminaba la edición) se mueven además `columns`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shift {
+    pub end_line: usize,
+    pub lines: isize,
+    pub columns: isize,
+}
+
+impl Shift {
+    fn position(&self, (line, column): (usize, usize)) -> (usize, usize) {
+        let column = if line == self.end_line { (column as isize + self.columns) as usize } else { column };
+        ((line as isize + self.lines) as usize, column)
+    }
+}
+
+// Documento abierto en el editor. Guarda el texto y sus tokens para que cada edición
+// re-escanee solo la región afectada en lugar de todo el archivo.
+pub struct Document {
+    text: String,
+    dialect: Dialect,
+    line_starts: Vec<usize>,
+    tokens: Vec<Token>,
+    errors: Vec<Token>,
+}
+
+fn position(token: &Token) -> (usize, usize) {
+    (token.2, token.3)
+}
+
+fn compute_line_starts(text: &str) -> Vec<usize> {
+    let mut line_starts = vec![0];
+    line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+    line_starts
+}
+
+// Posición final (en coordenadas nuevas) del texto insertado a partir de `start`
+fn end_of_insertion(start: (usize, usize), text: &str) -> (usize, usize) {
+    match text.rfind('\n') {
+        Some(last) => (start.0 + text.matches('\n').count(), text[last + 1..].chars().count() + 1),
+        None => (start.0, start.1 + text.chars().count()),
+    }
+}
+
+impl Document {
+    pub fn new(text: String, dialect: Dialect) -> Self {
+        let line_starts = compute_line_starts(&text);
+        let mut scanner = Scanner::new(&text).with_dialect(&dialect);
+        let tokens = scanner.by_ref().collect();
+        let errors = scanner.into_errors();
+        Document { text, dialect, line_starts, tokens, errors }
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn errors(&self) -> &[Token] {
+        &self.errors
+    }
+
+    // Desplazamiento en bytes de una posición (línea, columna); se ajusta al final de la línea si la excede
+    fn offset(&self, line: usize, column: usize) -> usize {
+        let line_index = line.clamp(1, self.line_starts.len()) - 1;
+        let line_start = self.line_starts[line_index];
+        let line_text = self.text[line_start..].split('\n').next().unwrap_or("");
+        let within = line_text
+            .char_indices()
+            .nth(column.saturating_sub(1))
+            .map(|(i, _)| i)
+            .unwrap_or(line_text.len());
+        line_start + within
+    }
+
+    // Aplica una edición y re-escanea desde el último token anterior a ella hasta que
+    // los nuevos tokens vuelven a coincidir con los antiguos (desplazados) después de la edición
+    pub fn apply_edit(&mut self, edit: &TextEdit) -> TokenDelta {
+        let mut old_start = (edit.start_line, edit.start_column);
+        let mut old_end = (edit.end_line, edit.end_column);
+        if old_end < old_start {
+            std::mem::swap(&mut old_start, &mut old_end);
+        }
+        let new_end = end_of_insertion(old_start, &edit.text);
+
+        // Se reinicia desde el token que precede a la edición: pudo unirse con el texto nuevo (p. ej. "=" + "=")
+        let first_touched = self.tokens.partition_point(|t| position(t) < old_start);
+        let restart = first_touched.saturating_sub(1);
+        let restart_position = match first_touched {
+            0 => (1, 1),
+            _ => position(&self.tokens[restart]),
+        };
+        let first_after = self.tokens.partition_point(|t| position(t) < old_end);
+
+        let shift = Shift {
+            end_line: old_end.0,
+            lines: new_end.0 as isize - old_end.0 as isize,
+            columns: new_end.1 as isize - old_end.1 as isize,
+        };
+
+        let start_offset = self.offset(old_start.0, old_start.1);
+        let end_offset = self.offset(old_end.0, old_end.1);
+        let restart_offset = self.offset(restart_position.0, restart_position.1);
+        self.text.replace_range(start_offset..end_offset, &edit.text);
+        self.line_starts = compute_line_starts(&self.text);
+
+        let mut scanner = Scanner::starting_at(&self.text[restart_offset..], restart_position.0, restart_position.1)
+            .with_dialect(&self.dialect);
+        let mut inserted = Vec::new();
+        let mut old_index = first_after;
+        let mut sync = None;
+        for token in scanner.by_ref() {
+            let pos = position(&token);
+            if pos >= new_end {
+                while old_index < self.tokens.len() && shift.position(position(&self.tokens[old_index])) < pos {
+                    old_index += 1;
+                }
+                // Desde una frontera de token el resto del texto no cambió: el escaneo ya se resincronizó
+                if old_index < self.tokens.len() && shift.position(position(&self.tokens[old_index])) == pos {
+                    sync = Some((old_index, pos));
+                    break;
+                }
+            }
+            inserted.push(token);
+        }
+
+        let mut errors: Vec<Token> = self.errors.iter().filter(|e| position(e) < restart_position).cloned().collect();
+        errors.extend(scanner.errors().iter().cloned());
+        let deleted = match sync {
+            Some((old_index, sync_position)) => {
+                for token in self.tokens[old_index..].iter_mut() {
+                    let (line, column) = shift.position(position(token));
+                    token.2 = line;
+                    token.3 = column;
+                }
+                for error in self.errors.iter().filter(|e| position(e) >= old_end) {
+                    let (line, column) = shift.position(position(error));
+                    if (line, column) >= sync_position {
+                        errors.push((error.0.clone(), error.1.clone(), line, column));
+                    }
+                }
+                old_index - restart
+            }
+            None => self.tokens.len() - restart,
+        };
+        self.tokens.splice(restart..restart + deleted, inserted.iter().cloned());
+        self.errors = errors;
+
+        TokenDelta {
+            start: restart,
+            deleted,
+            inserted,
+            shift,
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::ENGLISH;
+    use crate::scan::get_token;
+
+    const SOURCE: &str = "main() {\n    int x, y;\n    x = 10;\n    y = x / 2; // mitad\n    cout x;\n}\n";
+
+    fn edit(start: (usize, usize), end: (usize, usize), text: &str) -> TextEdit {
+        TextEdit {
+            start_line: start.0,
+            start_column: start.1,
+            end_line: end.0,
+            end_column: end.1,
+            text: text.to_string(),
+        }
+    }
+
+    // Aplica las ediciones en orden y compara después de cada una con el escaneo completo del texto
+    fn check(source: &str, edits: &[TextEdit]) {
+        let mut document = Document::new(source.to_string(), ENGLISH.clone());
+        for edit in edits {
+            let before = document.tokens().to_vec();
+            let delta = document.apply_edit(edit);
+            let (tokens, errors) = get_token(&document.text, &ENGLISH);
+            assert_eq!(document.tokens(), &tokens[..], "tokens tras {:?} en {:?}", edit, document.text);
+            assert_eq!(document.errors(), &errors[..], "errores tras {:?} en {:?}", edit, document.text);
+            // El delta aplicado a los tokens anteriores, como lo hace el editor, da la lista nueva
+            let mut patched = before;
+            let tail = delta.start + delta.inserted.len();
+            patched.splice(delta.start..delta.start + delta.deleted, delta.inserted);
+            for token in patched[tail..].iter_mut() {
+                let (line, column) = delta.shift.position(position(token));
+                token.2 = line;
+                token.3 = column;
+            }
+            assert_eq!(patched, tokens);
+            assert_eq!(delta.errors, errors);
+        }
+    }
+
+    #[test]
+    fn insert_inside_token() {
+        // "10" pasa a "1050" y "x" a "xa"
+        check(SOURCE, &[edit((3, 10), (3, 10), "05"), edit((5, 11), (5, 11), "a")]);
+        // '=' + '=' forma un solo token
+        check(SOURCE, &[edit((3, 8), (3, 8), "=")]);
+    }
+
+    #[test]
+    fn open_and_close_block_comment() {
+        check(SOURCE, &[edit((3, 5), (3, 5), "/*"), edit((4, 15), (4, 15), "*/")]);
+        // Comentario sin cerrar hasta el final del archivo
+        check(SOURCE, &[edit((2, 5), (2, 5), "/*")]);
+        // Se borra la apertura y el resto vuelve a ser código
+        check("main() {\n/* int x;\nx = 1; */\n}\n", &[edit((2, 1), (2, 3), "")]);
+    }
+
+    #[test]
+    fn delete_across_lines() {
+        check(SOURCE, &[edit((2, 10), (4, 10), "")]);
+        check(SOURCE, &[edit((4, 5), (2, 5), "")]);
+        check(SOURCE, &[edit((3, 1), (6, 1), "x = 1;\n")]);
+    }
+
+    #[test]
+    fn edit_at_start_and_end() {
+        check(SOURCE, &[edit((1, 1), (1, 1), "int g;\n")]);
+        check(SOURCE, &[edit((1, 1), (1, 5), "")]);
+        check(SOURCE, &[edit((7, 1), (7, 1), "int z; @")]);
+        check(SOURCE, &[edit((6, 1), (7, 1), "")]);
+    }
+
+    // Los tokens posteriores a la edición cambian de línea y, en la última línea editada, de columna
+    #[test]
+    fn shift_following_tokens() {
+        check(SOURCE, &[edit((2, 1), (2, 1), "\n\n")]);
+        check(SOURCE, &[edit((3, 5), (3, 5), "x = 1; ")]);
+        check(SOURCE, &[edit((3, 5), (3, 12), "")]);
+        check(SOURCE, &[edit((2, 14), (4, 5), " int z; z = 3;\n    ")]);
+    }
+
+    #[test]
+    fn lexical_errors_follow_the_edit() {
+        check("main() {\n    x = 1 @ 2;\n    y = $;\n}\n", &[edit((1, 1), (1, 1), "\n\n"), edit((3, 11), (3, 13), "+")]);
+    }
+}
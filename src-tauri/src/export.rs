@@ -0,0 +1,111 @@
+use crate::parse::TreeNode;
+
+// Representaciones del árbol sintáctico para reportes: Graphviz DOT (para `dot -Tsvg`),
+// expresiones S al estilo Lisp y texto con sangría como el de `tree(1)`.
+
+pub fn export(tree: &TreeNode, format: &str) -> Result<String, String> {
+    match format {
+        "dot" => Ok(to_dot(tree)),
+        "sexpr" => Ok(to_sexpr(tree)),
+        "text" => Ok(to_text(tree)),
+        _ => Err(format!("Formato desconocido: {}", format)),
+    }
+}
+
+// Árbol que se puede imprimir como texto: el sintáctico y el anotado (semantic.rs)
+pub trait Tree: Sized {
+    fn label(&self) -> String;
+    fn children(&self) -> &[Self];
+}
+
+impl Tree for TreeNode {
+    fn label(&self) -> String {
+        label(self)
+    }
+
+    fn children(&self) -> &[Self] {
+        &self.children
+    }
+}
+
+// Tipo del nodo y, en las hojas, el token con su lexema
+fn label(node: &TreeNode) -> String {
+    match (&node.token, &node.value) {
+        (Some(token), Some(value)) => format!("{:?} {:?} {}", node.node_type, token, value),
+        _ => format!("{:?}", node.node_type),
+    }
+}
+
+pub fn to_dot(tree: &TreeNode) -> String {
+    let mut dot = String::from("digraph syntax {\n    node [shape=box];\n");
+    let mut next_id = 0;
+    write_dot(tree, &mut next_id, &mut dot);
+    dot.push_str("}\n");
+    dot
+}
+
+// Escribe el nodo y sus aristas; devuelve el identificador que se le asignó
+fn write_dot(node: &TreeNode, next_id: &mut usize, dot: &mut String) -> usize {
+    let id = *next_id;
+    *next_id += 1;
+    let style = if node.token.is_some() { ", style=rounded" } else { "" };
+    dot.push_str(&format!("    n{} [label={}{}];\n", id, quote(&label(node)), style));
+    for child in &node.children {
+        let child_id = write_dot(child, next_id, dot);
+        dot.push_str(&format!("    n{} -> n{};\n", id, child_id));
+    }
+    id
+}
+
+// (IfStatement (Expression (Factor ID "x") (Factor LT "<") (Factor NumInt "1")) ...)
+pub fn to_sexpr(tree: &TreeNode) -> String {
+    let mut sexpr = String::new();
+    write_sexpr(tree, &mut sexpr);
+    sexpr.push('\n');
+    sexpr
+}
+
+fn write_sexpr(node: &TreeNode, sexpr: &mut String) {
+    sexpr.push('(');
+    sexpr.push_str(&format!("{:?}", node.node_type));
+    if let (Some(token), Some(value)) = (&node.token, &node.value) {
+        sexpr.push_str(&format!(" {:?} {}", token, quote(value)));
+    }
+    for child in &node.children {
+        sexpr.push(' ');
+        write_sexpr(child, sexpr);
+    }
+    sexpr.push(')');
+}
+
+// MainRoot
+// └── MainFunction
+//     ├── IntStatement
+//     │   └── Factor ID x
+pub fn to_text<T: Tree>(tree: &T) -> String {
+    let mut text = tree.label();
+    text.push('\n');
+    write_text_children(tree, "", &mut text);
+    text
+}
+
+fn write_text_children<T: Tree>(node: &T, prefix: &str, text: &mut String) {
+    for (i, child) in node.children().iter().enumerate() {
+        let last = i + 1 == node.children().len();
+        text.push_str(prefix);
+        text.push_str(if last { "└── " } else { "├── " });
+        text.push_str(&child.label());
+        text.push('\n');
+        let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        write_text_children(child, &prefix, text);
+    }
+}
+
+// Cadena entre comillas con '"' y '\' escapados, válida en DOT y en expresiones S
+fn quote(text: &str) -> String {
+    format!("\"{}\"", escape(text))
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+use serde::Serialize;
+use crate::ast::{BinOp, Expr, Program, Span, Stmt};
+
+// Plegado de constantes: evalúa en tiempo de compilación las expresiones formadas solo por
+// literales. `int` es de 32 bits con signo, como en C; los resultados que no caben se
+// reportan como desbordamiento. semantic.rs usa `evaluate` para conocer el valor de cada
+// expresión y reportar los errores, y `fold_program` reemplaza en el árbol tipado cada
+// subexpresión constante por su literal.
+
+// Valor de una expresión constante
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Int(i32),
+    Double(f64),
+    Bool(bool),
+}
+
+// Por qué una operación entre constantes no tiene valor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    // División, '%' o potencia negativa de cero
+    DivisionByZero,
+    // El resultado entero no cabe en 32 bits
+    Overflow,
+    // La operación no se puede evaluar (por ejemplo '%' con un double, que ya es un error de tipos)
+    NotConstant,
+}
+
+impl Value {
+    fn as_int(self) -> Option<i32> {
+        match self {
+            Value::Int(n) => Some(n),
+            Value::Bool(b) => Some(b as i32),
+            Value::Double(_) => None,
+        }
+    }
+
+    fn as_double(self) -> f64 {
+        match self {
+            Value::Int(n) => n as f64,
+            Value::Double(x) => x,
+            Value::Bool(b) => b as i32 as f64,
+        }
+    }
+
+    pub fn is_true(self) -> bool {
+        self.as_double() != 0.0
+    }
+
+    fn is_zero(self) -> bool {
+        self.as_double() == 0.0
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            // Con Debug un double entero se muestra como `3.0` y no como `3`
+            Value::Double(x) => write!(f, "{:?}", x),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+// Valor de un literal; un entero que no cabe en 32 bits es un desbordamiento
+pub fn literal(expr: &Expr) -> Option<Result<Value, Fault>> {
+    match expr {
+        Expr::Int { value, .. } => Some(value.parse().map(Value::Int).map_err(|_| Fault::Overflow)),
+        Expr::Real { value, .. } => Some(value.parse().map(Value::Double).map_err(|_| Fault::NotConstant)),
+        _ => None,
+    }
+}
+
+// Resultado de `left op right`. Con un operando double la operación es real; si no, entera
+// (un bool cuenta como 0 o 1).
+pub fn evaluate(op: BinOp, left: Value, right: Value) -> Result<Value, Fault> {
+    let real = matches!(left, Value::Double(_)) || matches!(right, Value::Double(_));
+    match op {
+        BinOp::Mod if real => Err(Fault::NotConstant),
+        _ if divides_by_zero(op, right) => Err(Fault::DivisionByZero),
+        BinOp::Pow if left.is_zero() && right.as_double() < 0.0 => Err(Fault::DivisionByZero),
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Pow if real => {
+            let (a, b) = (left.as_double(), right.as_double());
+            let x = match op {
+                BinOp::Add => a + b,
+                BinOp::Sub => a - b,
+                BinOp::Mul => a * b,
+                BinOp::Div => a / b,
+                _ => a.powf(b),
+            };
+            // Infinito o NaN (por ejemplo la raíz de un negativo) no son constantes útiles
+            if x.is_finite() {
+                Ok(Value::Double(x))
+            } else {
+                Err(Fault::NotConstant)
+            }
+        }
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod | BinOp::Pow => {
+            let (a, b) = (left.as_int().ok_or(Fault::NotConstant)?, right.as_int().ok_or(Fault::NotConstant)?);
+            let n = match op {
+                BinOp::Add => a.checked_add(b),
+                BinOp::Sub => a.checked_sub(b),
+                BinOp::Mul => a.checked_mul(b),
+                BinOp::Div => a.checked_div(b),
+                BinOp::Mod => a.checked_rem(b),
+                _ => power(a, b),
+            };
+            n.map(Value::Int).ok_or(Fault::Overflow)
+        }
+        BinOp::Lt | BinOp::Lte | BinOp::Gt | BinOp::Gte | BinOp::Eq | BinOp::Neq => {
+            let ordering = match (real, left.as_int(), right.as_int()) {
+                (false, Some(a), Some(b)) => a.cmp(&b),
+                _ => left.as_double().partial_cmp(&right.as_double()).ok_or(Fault::NotConstant)?,
+            };
+            Ok(Value::Bool(match op {
+                BinOp::Lt => ordering.is_lt(),
+                BinOp::Lte => ordering.is_le(),
+                BinOp::Gt => ordering.is_gt(),
+                BinOp::Gte => ordering.is_ge(),
+                BinOp::Eq => ordering.is_eq(),
+                _ => ordering.is_ne(),
+            }))
+        }
+        BinOp::And => Ok(Value::Bool(left.is_true() && right.is_true())),
+        BinOp::Or => Ok(Value::Bool(left.is_true() || right.is_true())),
+    }
+}
+
+// `left op right` divide entre cero sea cual sea `left`: '/' o '%' con divisor constante cero
+pub fn divides_by_zero(op: BinOp, right: Value) -> bool {
+    matches!(op, BinOp::Div | BinOp::Mod) && right.is_zero()
+}
+
+// Potencia entera; con exponente negativo el resultado se trunca hacia cero como en la
+// división entera (2 ^ -1 = 0), salvo para las bases 1 y -1. None si se desborda.
+fn power(base: i32, exponent: i32) -> Option<i32> {
+    if exponent >= 0 {
+        return base.checked_pow(exponent as u32);
+    }
+    match base {
+        1 => Some(1),
+        -1 => Some(if exponent % 2 == 0 { 1 } else { -1 }),
+        _ => Some(0),
+    }
+}
+
+// Copia del programa con cada subexpresión constante reemplazada por su literal. Las
+// comparaciones y `&`, `|` constantes quedan como 1 o 0, que es como se usan los bool. Las
+// operaciones con error (división por cero, desbordamiento) se dejan sin plegar.
+pub fn fold_program(program: &Program) -> Program {
+    Program {
+        items: fold_statements(&program.items),
+    }
+}
+
+fn fold_statements(statements: &[Stmt]) -> Vec<Stmt> {
+    statements.iter().map(fold_statement).collect()
+}
+
+fn fold_statement(statement: &Stmt) -> Stmt {
+    match statement {
+        Stmt::Main { body, span } => Stmt::Main { body: fold_statements(body), span: *span },
+        Stmt::Assign { target, value, span } => Stmt::Assign { target: target.clone(), value: fold_expr(value), span: *span },
+        Stmt::If { cond, then, else_, span } => Stmt::If {
+            cond: fold_expr(cond),
+            then: fold_statements(then),
+            else_: else_.as_deref().map(fold_statements),
+            span: *span,
+        },
+        Stmt::While { cond, body, span } => Stmt::While { cond: fold_expr(cond), body: fold_statements(body), span: *span },
+        Stmt::DoWhile { body, cond, span } => Stmt::DoWhile { body: fold_statements(body), cond: fold_expr(cond), span: *span },
+        Stmt::RepeatUntil { body, cond, span } => Stmt::RepeatUntil { body: fold_statements(body), cond: fold_expr(cond), span: *span },
+        Stmt::Cout { value, span } => Stmt::Cout { value: fold_expr(value), span: *span },
+        Stmt::Return { value, span } => Stmt::Return { value: fold_expr(value), span: *span },
+        other => other.clone(),
+    }
+}
+
+fn fold_expr(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Binary { op, left, right, span } => {
+            let (left, right) = (fold_expr(left), fold_expr(right));
+            let value = match (literal(&left), literal(&right)) {
+                (Some(Ok(a)), Some(Ok(b))) => evaluate(*op, a, b).ok(),
+                _ => None,
+            };
+            match value {
+                Some(value) => to_literal(value, expr.start()),
+                None => Expr::Binary { op: *op, left: Box::new(left), right: Box::new(right), span: *span },
+            }
+        }
+        Expr::Group { inner, span } => match fold_expr(inner) {
+            inner @ (Expr::Int { .. } | Expr::Real { .. }) => inner,
+            inner => Expr::Group { inner: Box::new(inner), span: *span },
+        },
+        other => other.clone(),
+    }
+}
+
+// Literal con el valor; toma la posición del inicio de la expresión que reemplaza
+fn to_literal(value: Value, span: Span) -> Expr {
+    match value {
+        Value::Int(n) => Expr::Int { value: n.to_string(), span },
+        Value::Double(x) => Expr::Real { value: format!("{:?}", x), span },
+        Value::Bool(b) => Expr::Int { value: (b as i32).to_string(), span },
+    }
+}
@@ -0,0 +1,332 @@
+use serde::{Serialize, Deserialize};
+use crate::globals::TokenType;
+use crate::globals::NodeType;
+use crate::dialect::Dialect;
+use crate::diagnostic::Diagnostic;
+use crate::locale::{Locale, Text};
+use crate::cst::{SyntaxNode, SyntaxElement, TokenKind};
+use crate::parse;
+
+// Formateador: recorre el CST y vuelve a escribir el programa con el estilo canónico.
+// Una sentencia por línea, una sangría por bloque, espacios alrededor de los operadores
+// y los comentarios en su lugar. Solo cambian los espacios, nunca los tokens.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BraceStyle {
+    // `if (x) {` y `} else {`
+    SameLine,
+    // La llave en su propia línea, alineada con la sentencia
+    NextLine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatOptions {
+    pub indent_width: usize,
+    pub brace_style: BraceStyle,
+}
+
+pub const NOT_FORMATTED: Text = Text {
+    es: "No se formateó el programa porque tiene errores:",
+    en: "The program was not formatted because it has errors:",
+};
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent_width: 4,
+            brace_style: BraceStyle::SameLine,
+        }
+    }
+}
+
+impl BraceStyle {
+    pub fn from_name(name: &str) -> Result<BraceStyle, String> {
+        match name {
+            "same-line" => Ok(BraceStyle::SameLine),
+            "next-line" => Ok(BraceStyle::NextLine),
+            _ => Err(format!("Estilo de llaves desconocido: {}", name)),
+        }
+    }
+}
+
+// Formatea el programa. Con errores léxicos o de sintaxis no se toca el texto: se devuelven
+// los errores, porque el árbol recuperado podría no corresponder a lo que se quiso escribir.
+pub fn format(source: &str, dialect: &Dialect, options: &FormatOptions, locale: Locale) -> Result<String, Vec<Diagnostic>> {
+    let (result, errors) = parse::parse_source(source, dialect, locale);
+    let diagnostics: Vec<Diagnostic> = errors
+        .iter()
+        .map(|error| Diagnostic::lexical(error, locale))
+        .chain(result.errors)
+        .map(|diagnostic| diagnostic.with_snippet(source))
+        .collect();
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    let mut printer = Printer {
+        options,
+        out: String::with_capacity(source.len()),
+        indent: 0,
+        at_line_start: true,
+        previous: None,
+        after_comment: false,
+        same_line: false,
+        newlines: 0,
+    };
+    printer.node(&SyntaxNode::new_root(result.green));
+    printer.newline();
+    Ok(printer.out)
+}
+
+struct Printer<'a> {
+    options: &'a FormatOptions,
+    out: String,
+    indent: usize,
+    // Todavía no se escribió nada en la línea actual
+    at_line_start: bool,
+    // Último token escrito (sin contar comentarios)
+    previous: Option<TokenType>,
+    after_comment: bool,
+    // La próxima sentencia sigue en la línea actual (el if de un `else if`)
+    same_line: bool,
+    // Saltos de línea del texto original desde el último token o comentario
+    newlines: usize,
+}
+
+// Nodos que se escriben en su propia línea
+fn is_statement(kind: &NodeType) -> bool {
+    !matches!(
+        kind,
+        NodeType::MainRoot | NodeType::Expression | NodeType::Term | NodeType::Factor | NodeType::Error | NodeType::ElseStatement
+    )
+}
+
+// Nodos cuyo cuerpo es un bloque, o una sola sentencia si no lleva llaves
+fn has_body(kind: &NodeType) -> bool {
+    matches!(
+        kind,
+        NodeType::IfStatement
+            | NodeType::ElseStatement
+            | NodeType::WhileStatement
+            | NodeType::DoWhileStatement
+            | NodeType::RepeatUntilStatement
+            | NodeType::MainFunction
+    )
+}
+
+// Sentencia que es el cuerpo de `parent` sin llaves: va en la línea siguiente con una
+// sangría más, salvo el if de un `else if`
+fn is_braceless_body(parent: &NodeType, child: &NodeType) -> bool {
+    has_body(parent) && is_statement(child) && !(*parent == NodeType::ElseStatement && *child == NodeType::IfStatement)
+}
+
+impl Printer<'_> {
+    fn node(&mut self, node: &SyntaxNode) {
+        let kind = node.kind();
+        if is_statement(&kind) {
+            self.start_statement();
+        }
+        let mut open_braces = 0;
+        // El último elemento propio del nodo fue la '}' de su cuerpo
+        let mut closed_body = false;
+        let children = node.children_with_tokens();
+        for (i, child) in children.iter().enumerate() {
+            match child {
+                SyntaxElement::Node(child) => {
+                    if child.kind() == NodeType::ElseStatement {
+                        self.after_body(closed_body);
+                    }
+                    let braceless = open_braces == 0 && is_braceless_body(&kind, &child.kind());
+                    // `else if` se escribe en una sola línea
+                    self.same_line = open_braces == 0 && kind == NodeType::ElseStatement && child.kind() == NodeType::IfStatement;
+                    if braceless {
+                        self.indent += 1;
+                    }
+                    self.node(child);
+                    if braceless {
+                        self.indent -= 1;
+                    }
+                    closed_body = false;
+                }
+                SyntaxElement::Token(token) => match token.kind() {
+                    TokenKind::Whitespace => self.newlines += token.text().matches('\n').count(),
+                    TokenKind::Comment => {
+                        // Un comentario antes de un cuerpo sin llaves lleva la sangría del cuerpo
+                        let next = children[i + 1..].iter().find(|next| match next {
+                            SyntaxElement::Token(token) => !token.kind().is_trivia(),
+                            SyntaxElement::Node(_) => true,
+                        });
+                        let braceless = open_braces == 0
+                            && matches!(next, Some(SyntaxElement::Node(next)) if is_braceless_body(&kind, &next.kind()));
+                        if braceless {
+                            self.indent += 1;
+                        }
+                        self.comment(token.text());
+                        if braceless {
+                            self.indent -= 1;
+                        }
+                    }
+                    // Sin errores léxicos no hay texto desconocido; se conserva por si acaso
+                    TokenKind::Unknown => self.write(token.text(), true),
+                    TokenKind::Token(token_type) => {
+                        match token_type {
+                            TokenType::LBRACE => open_braces += 1,
+                            TokenType::RBRACE => open_braces -= 1,
+                            // El `while`/`until` final de un do-while o repeat-until
+                            TokenType::WHILE | TokenType::UNTIL
+                                if matches!(kind, NodeType::DoWhileStatement | NodeType::RepeatUntilStatement) =>
+                            {
+                                self.after_body(closed_body)
+                            }
+                            _ => {}
+                        }
+                        self.token(token_type, token.text());
+                        closed_body = *token_type == TokenType::RBRACE;
+                    }
+                },
+            }
+        }
+    }
+
+    // `} else` y `} while` van en la misma línea si el cuerpo anterior cerró con llave
+    fn after_body(&mut self, closed_body: bool) {
+        if !(self.options.brace_style == BraceStyle::SameLine && closed_body) {
+            self.newline();
+        }
+    }
+
+    fn token(&mut self, token_type: &TokenType, text: &str) {
+        match token_type {
+            TokenType::ENDFILE => return,
+            TokenType::LBRACE => {
+                if self.options.brace_style == BraceStyle::NextLine {
+                    self.newline();
+                }
+                self.write(text, true);
+                self.indent += 1;
+            }
+            TokenType::RBRACE => {
+                self.indent -= 1;
+                self.newline();
+                self.write(text, false);
+            }
+            _ => {
+                let space = self.space_before(token_type);
+                self.write(text, space);
+            }
+        }
+        self.previous = Some(token_type.clone());
+        self.after_comment = false;
+        self.newlines = 0;
+    }
+
+    fn space_before(&self, token_type: &TokenType) -> bool {
+        if self.after_comment {
+            return true;
+        }
+        match token_type {
+            TokenType::SEMICOLON | TokenType::COMMA | TokenType::RPAREN | TokenType::INCREMENT | TokenType::DECREMENT => false,
+            TokenType::LPAREN => !matches!(self.previous, Some(TokenType::MAIN) | Some(TokenType::LPAREN)),
+            _ => self.previous != Some(TokenType::LPAREN),
+        }
+    }
+
+    // Un comentario que estaba solo en su línea sigue solo; uno al final de una línea se
+    // queda al final de la línea que le corresponde en el texto formateado
+    fn comment(&mut self, text: &str) {
+        if self.newlines > 0 {
+            self.start_statement();
+        }
+        self.write(text.trim_end(), true);
+        if text.starts_with("//") {
+            self.newline();
+        }
+        self.after_comment = true;
+        self.newlines = 0;
+    }
+
+    // Empieza una línea nueva; conserva una línea en blanco si el texto original tenía al
+    // menos una, salvo al inicio del archivo o de un bloque
+    fn start_statement(&mut self) {
+        if std::mem::take(&mut self.same_line) {
+            return;
+        }
+        self.newline();
+        if self.newlines >= 2 && !self.out.is_empty() && !self.out.ends_with("\n\n") && self.previous != Some(TokenType::LBRACE) {
+            self.out.push('\n');
+        }
+        self.newlines = 0;
+    }
+
+    fn newline(&mut self) {
+        if !self.at_line_start {
+            self.out.push('\n');
+            self.at_line_start = true;
+        }
+    }
+
+    fn write(&mut self, text: &str, space: bool) {
+        if self.at_line_start {
+            self.out.push_str(&" ".repeat(self.indent * self.options.indent_width));
+        } else if space {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.at_line_start = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codes;
+    use crate::dialect::ENGLISH;
+
+    const MESSY: &str = "// programa\nint   g;\nmain(){int x,y; /* dos */\nx=1+2*3;if(x>=2){cout x;}else{y=x^2; // cuadrado\n}\nwhile (x<10) { x++; }\ndo { x--; } while (x > 0);\nrepeat{y=y-1;}until(y==0);}\n";
+
+    fn format_with(source: &str, options: &FormatOptions) -> String {
+        format(source, &ENGLISH, options, Locale::Es).unwrap()
+    }
+
+    fn codes(source: &str) -> Vec<String> {
+        format(source, &ENGLISH, &FormatOptions::default(), Locale::Es)
+            .unwrap_err()
+            .into_iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect()
+    }
+
+    #[test]
+    fn is_idempotent() {
+        let next_line = FormatOptions { indent_width: 2, brace_style: BraceStyle::NextLine };
+        for options in [FormatOptions::default(), next_line] {
+            let once = format_with(MESSY, &options);
+            assert_eq!(format_with(&once, &options), once);
+        }
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let formatted = format_with(MESSY, &FormatOptions::default());
+        for comment in ["// programa", "/* dos */", "// cuadrado"] {
+            assert!(formatted.contains(comment), "falta {} en:\n{}", comment, formatted);
+        }
+    }
+
+    #[test]
+    fn else_if_stays_on_one_line() {
+        let source = "main(){int x;if(x==1){x=2;}else if(x==2){x=3;}else{x=1;}}";
+        let same_line = "main() {\n    int x;\n    if (x == 1) {\n        x = 2;\n    } else if (x == 2) {\n        x = 3;\n    } else {\n        x = 1;\n    }\n}\n";
+        assert_eq!(format_with(source, &FormatOptions::default()), same_line);
+        let next_line = FormatOptions { indent_width: 2, brace_style: BraceStyle::NextLine };
+        let expected = "main()\n{\n  int x;\n  if (x == 1)\n  {\n    x = 2;\n  }\n  else if (x == 2)\n  {\n    x = 3;\n  }\n  else\n  {\n    x = 1;\n  }\n}\n";
+        assert_eq!(format_with(source, &next_line), expected);
+    }
+
+    #[test]
+    fn refuses_programs_with_errors() {
+        assert!(codes("main() { int x; x = 1 @ 2; }").contains(&codes::UNKNOWN_CHARACTER.to_string()));
+        assert!(codes("main() { int x; x = 1 }").contains(&codes::MISSING_SEMICOLON.to_string()));
+    }
+}
@@ -65,14 +65,26 @@ pub enum TokenType {
     InMultipleComment,
 }
 
-// Enum para representar los estados en el DFA del escáner
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+// Token producido por el escáner: (tipo, lexema, línea, columna)
+pub type Token = (TokenType, String, usize, usize);
+
+// Enum para representar los estados en el DFA del escáner (la tabla de transiciones está en dfa.rs)
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
 pub enum StateType {
     Start,
-    InAssign,
+    InAssign,            // se leyó '=', puede ser '=='
+    InLess,              // se leyó '<', puede ser '<='
+    InGreater,           // se leyó '>', puede ser '>='
+    InNot,               // se leyó '!', solo es válido como '!='
+    InPlus,              // se leyó '+', puede ser '++'
+    InMinus,             // se leyó '-', puede ser '--'
+    InSlash,             // se leyó '/', puede iniciar un comentario
     InComment,
     InMultiComment,
+    InMultiCommentStar,  // se leyó '*' dentro de un comentario múltiple
+    InMultiCommentSlash, // se leyó '/' dentro de un comentario múltiple
     InNum,
+    InPoint,             // se leyó el '.' de un número real, falta al menos un dígito
     InReal,
     InId,
     Done,
@@ -0,0 +1,605 @@
+use std::collections::{BTreeMap, BTreeSet};
+use serde::Serialize;
+use crate::globals::TokenType;
+use crate::dialect;
+use crate::diagnostic::Diagnostic;
+use crate::locale::Locale;
+use crate::scan;
+use crate::parse;
+
+// Especificación de la gramática (grammar.ebnf) y las comprobaciones que la mantienen al
+// día con parse.rs: conjuntos FIRST/FOLLOW, conflictos LL(1) y oraciones de ejemplo que
+// el parser debe aceptar.
+
+pub const SOURCE: &str = include_str!("../grammar.ebnf");
+
+// Clases de token del escáner que la gramática usa como terminales, con lexemas de ejemplo
+const TOKEN_CLASSES: [(&str, &[&str]); 3] = [
+    ("ID", &["x", "y", "total"]),
+    ("NumInt", &["0", "7", "42"]),
+    ("NumReal", &["2.5", "0.75"]),
+];
+
+// Conflictos conocidos que parse.rs resuelve a propósito: (regla, token). El else ambiguo
+// se asocia al if más cercano porque parse_if_statement lo consume en cuanto lo ve.
+const RESOLVED: [(&str, &str); 1] = [("if_statement", "else")];
+
+// Fin de archivo en los conjuntos FOLLOW
+const END: &str = "EOF";
+// Cadena vacía en los conjuntos FIRST
+const EMPTY: &str = "ε";
+
+// Oraciones de ejemplo por comprobación y profundidad a partir de la cual se elige
+// siempre la alternativa más corta para que la derivación termine
+const EXAMPLES: usize = 200;
+const MAX_DEPTH: usize = 8;
+
+// Expresión del lado derecho de una regla. Los nombres de las variantes son los de las
+// bibliotecas de diagramas de sintaxis (railroad diagrams), así que el JSON se dibuja directo.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type")]
+pub enum Element {
+    Terminal { text: String },
+    NonTerminal { text: String },
+    Sequence { items: Vec<Element> },
+    Choice { items: Vec<Element> },
+    Optional { item: Box<Element> },
+    ZeroOrMore { item: Box<Element> },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Rule {
+    pub name: String,
+    pub body: Element,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Grammar {
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    // Regla de grammar.ebnf donde está el conflicto
+    pub rule: String,
+    // "first/first" o "first/follow"
+    pub kind: String,
+    pub tokens: Vec<String>,
+    // Conflicto conocido que el parser resuelve (ver RESOLVED)
+    pub resolved: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Example {
+    pub sentence: String,
+    pub errors: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub first: BTreeMap<String, Vec<String>>,
+    pub follow: BTreeMap<String, Vec<String>>,
+    pub conflicts: Vec<Conflict>,
+    // Reglas sin definir, inalcanzables o terminales que el escáner no reconoce
+    pub problems: Vec<String>,
+    pub examples: Vec<Example>,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.conflicts.iter().all(|conflict| conflict.resolved) && self.problems.is_empty() && self.examples.iter().all(|example| example.errors.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EbnfToken {
+    Name(String),
+    Quoted(String),
+    Symbol(char),
+}
+
+// Lee la notación de grammar.ebnf: `nombre = expresión ;` con | { } [ ] ( ) y
+// comentarios (* ... *)
+pub fn parse_ebnf(source: &str) -> Result<Grammar, String> {
+    let tokens = tokenize_ebnf(source)?;
+    let mut current = 0;
+    let mut rules: Vec<Rule> = Vec::new();
+    while current < tokens.len() {
+        let name = match &tokens[current] {
+            EbnfToken::Name(name) => name.clone(),
+            token => return Err(format!("Se esperaba el nombre de una regla, se encontró {:?}", token)),
+        };
+        if rules.iter().any(|rule| rule.name == name) {
+            return Err(format!("La regla '{}' está definida dos veces", name));
+        }
+        current += 1;
+        expect_symbol(&tokens, &mut current, '=')?;
+        let body = parse_choice(&tokens, &mut current)?;
+        expect_symbol(&tokens, &mut current, ';')?;
+        rules.push(Rule { name, body });
+    }
+    if rules.is_empty() {
+        return Err("La gramática no tiene reglas".to_string());
+    }
+    Ok(Grammar { rules })
+}
+
+fn tokenize_ebnf(source: &str) -> Result<Vec<EbnfToken>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if rest.starts_with("(*") {
+            let end = rest.find("*)").ok_or("Comentario sin cerrar en la gramática")?;
+            rest = &rest[end + 2..];
+        } else if c == '"' {
+            let end = rest[1..].find('"').ok_or("Terminal sin cerrar en la gramática")?;
+            tokens.push(EbnfToken::Quoted(rest[1..end + 1].to_string()));
+            rest = &rest[end + 2..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            tokens.push(EbnfToken::Name(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if "=;|{}[]()".contains(c) {
+            tokens.push(EbnfToken::Symbol(c));
+            rest = &rest[1..];
+        } else {
+            return Err(format!("Carácter inesperado en la gramática: '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+fn expect_symbol(tokens: &[EbnfToken], current: &mut usize, symbol: char) -> Result<(), String> {
+    match tokens.get(*current) {
+        Some(EbnfToken::Symbol(c)) if *c == symbol => {
+            *current += 1;
+            Ok(())
+        }
+        other => Err(format!("Se esperaba '{}' en la gramática, se encontró {:?}", symbol, other)),
+    }
+}
+
+fn parse_choice(tokens: &[EbnfToken], current: &mut usize) -> Result<Element, String> {
+    let mut items = vec![parse_sequence(tokens, current)?];
+    while tokens.get(*current) == Some(&EbnfToken::Symbol('|')) {
+        *current += 1;
+        items.push(parse_sequence(tokens, current)?);
+    }
+    Ok(if items.len() == 1 { items.remove(0) } else { Element::Choice { items } })
+}
+
+fn parse_sequence(tokens: &[EbnfToken], current: &mut usize) -> Result<Element, String> {
+    let mut items = Vec::new();
+    loop {
+        let item = match tokens.get(*current) {
+            // Las clases de token empiezan con mayúscula; las reglas, con minúscula
+            Some(EbnfToken::Name(name)) if name.starts_with(|c: char| c.is_ascii_uppercase()) => Element::Terminal { text: name.clone() },
+            Some(EbnfToken::Name(name)) => Element::NonTerminal { text: name.clone() },
+            Some(EbnfToken::Quoted(text)) => Element::Terminal { text: text.clone() },
+            Some(EbnfToken::Symbol(open @ ('{' | '[' | '('))) => {
+                let close = match open {
+                    '{' => '}',
+                    '[' => ']',
+                    _ => ')',
+                };
+                *current += 1;
+                let inner = parse_choice(tokens, current)?;
+                expect_symbol(tokens, current, close)?;
+                items.push(match open {
+                    '{' => Element::ZeroOrMore { item: Box::new(inner) },
+                    '[' => Element::Optional { item: Box::new(inner) },
+                    _ => inner,
+                });
+                continue;
+            }
+            _ => break,
+        };
+        *current += 1;
+        items.push(item);
+    }
+    match items.len() {
+        0 => Err(format!("Alternativa vacía en la gramática cerca de {:?}", tokens.get(*current))),
+        1 => Ok(items.remove(0)),
+        _ => Ok(Element::Sequence { items }),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Symbol {
+    Terminal(String),
+    NonTerminal(String),
+}
+
+// Producción de la gramática en BNF. Las repeticiones, opciones y grupos del EBNF se
+// convierten en no terminales auxiliares ("regla#n"); `origin` es la regla de la que salen.
+#[derive(Debug, Clone)]
+struct Production {
+    head: String,
+    origin: String,
+    body: Vec<Symbol>,
+}
+
+fn to_bnf(grammar: &Grammar) -> Vec<Production> {
+    let mut productions = Vec::new();
+    for rule in &grammar.rules {
+        let mut helpers = 0;
+        let alternatives = match &rule.body {
+            Element::Choice { items } => items.iter().collect(),
+            body => vec![body],
+        };
+        for alternative in alternatives {
+            let body = desugar(alternative, &rule.name, &mut helpers, &mut productions);
+            productions.push(Production { head: rule.name.clone(), origin: rule.name.clone(), body });
+        }
+    }
+    productions
+}
+
+// Símbolos que reemplazan a `element`; agrega las producciones auxiliares que necesite
+fn desugar(element: &Element, origin: &str, helpers: &mut usize, productions: &mut Vec<Production>) -> Vec<Symbol> {
+    let bodies = match element {
+        Element::Terminal { text } => return vec![Symbol::Terminal(text.clone())],
+        Element::NonTerminal { text } => return vec![Symbol::NonTerminal(text.clone())],
+        Element::Sequence { items } => return items.iter().flat_map(|item| desugar(item, origin, helpers, productions)).collect(),
+        Element::Choice { items } => items.iter().map(|item| desugar(item, origin, helpers, productions)).collect(),
+        Element::Optional { item } | Element::ZeroOrMore { item } => vec![desugar(item, origin, helpers, productions), Vec::new()],
+    };
+    *helpers += 1;
+    let head = format!("{}#{}", origin, helpers);
+    for mut body in bodies {
+        // { x } es recursivo: x seguido otra vez de la repetición, o nada
+        if matches!(element, Element::ZeroOrMore { .. }) && !body.is_empty() {
+            body.push(Symbol::NonTerminal(head.clone()));
+        }
+        productions.push(Production { head: head.clone(), origin: origin.to_string(), body });
+    }
+    vec![Symbol::NonTerminal(head)]
+}
+
+struct Sets {
+    nullable: BTreeSet<String>,
+    first: BTreeMap<String, BTreeSet<String>>,
+    follow: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Sets {
+    // FIRST de una secuencia de símbolos y si puede derivar la cadena vacía
+    fn first_of(&self, symbols: &[Symbol]) -> (BTreeSet<String>, bool) {
+        let mut first = BTreeSet::new();
+        for symbol in symbols {
+            match symbol {
+                Symbol::Terminal(text) => {
+                    first.insert(text.clone());
+                    return (first, false);
+                }
+                Symbol::NonTerminal(name) => {
+                    first.extend(self.first.get(name).cloned().unwrap_or_default());
+                    if !self.nullable.contains(name) {
+                        return (first, false);
+                    }
+                }
+            }
+        }
+        (first, true)
+    }
+}
+
+// Punto fijo clásico: se repite hasta que ningún conjunto cambia
+fn compute_sets(productions: &[Production], start: &str) -> Sets {
+    let mut sets = Sets {
+        nullable: BTreeSet::new(),
+        first: BTreeMap::new(),
+        follow: BTreeMap::new(),
+    };
+    for production in productions {
+        sets.first.entry(production.head.clone()).or_default();
+        sets.follow.entry(production.head.clone()).or_default();
+    }
+    sets.follow.entry(start.to_string()).or_default().insert(END.to_string());
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for production in productions {
+            let (first, nullable) = sets.first_of(&production.body);
+            if nullable {
+                changed |= sets.nullable.insert(production.head.clone());
+            }
+            let entry = sets.first.entry(production.head.clone()).or_default();
+            let before = entry.len();
+            entry.extend(first);
+            changed |= entry.len() != before;
+        }
+    }
+    changed = true;
+    while changed {
+        changed = false;
+        for production in productions {
+            for (i, symbol) in production.body.iter().enumerate() {
+                if let Symbol::NonTerminal(name) = symbol {
+                    let (mut follow, nullable) = sets.first_of(&production.body[i + 1..]);
+                    if nullable {
+                        follow.extend(sets.follow.get(&production.head).cloned().unwrap_or_default());
+                    }
+                    let entry = sets.follow.entry(name.clone()).or_default();
+                    let before = entry.len();
+                    entry.extend(follow);
+                    changed |= entry.len() != before;
+                }
+            }
+        }
+    }
+    sets
+}
+
+// Una gramática es LL(1) si en cada no terminal las alternativas empiezan con tokens
+// distintos y, cuando una puede ser vacía, ninguna otra empieza con un token de su FOLLOW
+fn find_conflicts(productions: &[Production], sets: &Sets) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    let mut heads: Vec<&String> = Vec::new();
+    for production in productions {
+        if !heads.contains(&&production.head) {
+            heads.push(&production.head);
+        }
+    }
+    for head in heads {
+        let alternatives: Vec<&Production> = productions.iter().filter(|production| &production.head == head).collect();
+        let origin = alternatives[0].origin.clone();
+        let firsts: Vec<(BTreeSet<String>, bool)> = alternatives.iter().map(|production| sets.first_of(&production.body)).collect();
+        let follow = sets.follow.get(head).cloned().unwrap_or_default();
+        for i in 0..firsts.len() {
+            for j in i + 1..firsts.len() {
+                let mut shared: Vec<String> = firsts[i].0.intersection(&firsts[j].0).cloned().collect();
+                if firsts[i].1 && firsts[j].1 {
+                    shared.push(EMPTY.to_string());
+                }
+                if !shared.is_empty() {
+                    conflicts.push(conflict(&origin, "first/first", shared));
+                }
+            }
+            if firsts[i].1 {
+                for (j, (first, _)) in firsts.iter().enumerate() {
+                    let shared: Vec<String> = first.intersection(&follow).cloned().collect();
+                    if j != i && !shared.is_empty() {
+                        conflicts.push(conflict(&origin, "first/follow", shared));
+                    }
+                }
+            }
+        }
+    }
+    conflicts
+}
+
+fn conflict(rule: &str, kind: &str, tokens: Vec<String>) -> Conflict {
+    let resolved = tokens.iter().all(|token| RESOLVED.contains(&(rule, token.as_str())));
+    Conflict { rule: rule.to_string(), kind: kind.to_string(), tokens, resolved }
+}
+
+// Reglas usadas sin definir, reglas a las que no se llega desde la inicial y terminales
+// que el escáner no convierte en exactamente un token
+fn find_problems(grammar: &Grammar, productions: &[Production]) -> Vec<String> {
+    let mut problems = Vec::new();
+    let defined: BTreeSet<&str> = grammar.rules.iter().map(|rule| rule.name.as_str()).collect();
+    let mut reachable = BTreeSet::new();
+    let mut pending = vec![grammar.rules[0].name.clone()];
+    while let Some(name) = pending.pop() {
+        if !reachable.insert(name.clone()) {
+            continue;
+        }
+        for production in productions.iter().filter(|production| production.head == name) {
+            for symbol in &production.body {
+                match symbol {
+                    Symbol::NonTerminal(next) if !defined.contains(next.as_str()) && !next.contains('#') => {
+                        problems.push(format!("La regla '{}' usa '{}', que no está definida", production.origin, next));
+                    }
+                    Symbol::NonTerminal(next) => pending.push(next.clone()),
+                    Symbol::Terminal(text) => {
+                        if let Some(problem) = check_terminal(text) {
+                            problems.push(format!("En la regla '{}': {}", production.origin, problem));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for rule in &grammar.rules {
+        if !reachable.contains(&rule.name) {
+            problems.push(format!("La regla '{}' no se alcanza desde '{}'", rule.name, grammar.rules[0].name));
+        }
+    }
+    problems.sort();
+    problems.dedup();
+    problems
+}
+
+fn check_terminal(text: &str) -> Option<String> {
+    if text.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return match TOKEN_CLASSES.iter().any(|(class, _)| *class == text) {
+            true => None,
+            false => Some(format!("'{}' no es una clase de token del escáner", text)),
+        };
+    }
+    let (tokens, errors) = scan::get_token(text, &dialect::ENGLISH);
+    match tokens.as_slice() {
+        [(token, lexeme, _, _), (TokenType::ENDFILE, _, _, _)] if errors.is_empty() && lexeme == text && *token != TokenType::ID => None,
+        _ => Some(format!("el escáner no reconoce \"{}\" como un solo token", text)),
+    }
+}
+
+// Generador pseudoaleatorio xorshift: las oraciones son las mismas en cada ejecución
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+// Longitud (en terminales) de la oración más corta que deriva cada no terminal
+fn shortest_lengths(productions: &[Production]) -> BTreeMap<String, usize> {
+    let mut lengths: BTreeMap<String, usize> = BTreeMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for production in productions {
+            let length = production.body.iter().try_fold(0, |total, symbol| match symbol {
+                Symbol::Terminal(_) => Some(total + 1),
+                Symbol::NonTerminal(name) => lengths.get(name).map(|length| total + length),
+            });
+            if let Some(length) = length {
+                if lengths.get(&production.head).map_or(true, |current| length < *current) {
+                    lengths.insert(production.head.clone(), length);
+                    changed = true;
+                }
+            }
+        }
+    }
+    lengths
+}
+
+fn generate(symbol: &Symbol, productions: &[Production], lengths: &BTreeMap<String, usize>, depth: usize, random: &mut Random, out: &mut Vec<String>) {
+    match symbol {
+        Symbol::Terminal(text) => match TOKEN_CLASSES.iter().find(|(class, _)| class == text) {
+            Some((_, lexemes)) => out.push(lexemes[random.below(lexemes.len())].to_string()),
+            None => out.push(text.clone()),
+        },
+        Symbol::NonTerminal(name) => {
+            let alternatives: Vec<&Production> = productions.iter().filter(|production| &production.head == name).collect();
+            let length = |production: &Production| -> usize {
+                production
+                    .body
+                    .iter()
+                    .map(|symbol| match symbol {
+                        Symbol::Terminal(_) => 1,
+                        Symbol::NonTerminal(name) => lengths.get(name).copied().unwrap_or(usize::MAX / 64),
+                    })
+                    .sum()
+            };
+            let chosen = if depth >= MAX_DEPTH {
+                alternatives.iter().min_by_key(|production| length(production)).copied()
+            } else if alternatives.is_empty() {
+                None
+            } else {
+                Some(alternatives[random.below(alternatives.len())])
+            };
+            if let Some(production) = chosen {
+                for symbol in &production.body {
+                    generate(symbol, productions, lengths, depth + 1, random, out);
+                }
+            }
+        }
+    }
+}
+
+// Oraciones generadas desde la regla inicial, cada una con los errores que reporta el parser
+fn check_examples(grammar: &Grammar, productions: &[Production], locale: Locale) -> Vec<Example> {
+    let lengths = shortest_lengths(productions);
+    let start = Symbol::NonTerminal(grammar.rules[0].name.clone());
+    let mut random = Random(0x5eed);
+    let mut sentences = BTreeSet::new();
+    for _ in 0..EXAMPLES {
+        let mut words = Vec::new();
+        generate(&start, productions, &lengths, 0, &mut random, &mut words);
+        sentences.insert(words.join(" "));
+    }
+    sentences
+        .into_iter()
+        .map(|sentence| {
+            let (result, lexical) = parse::parse_source(&sentence, &dialect::ENGLISH, locale);
+            let errors = lexical
+                .iter()
+                .map(|error| Diagnostic::lexical(error, locale))
+                .chain(result.errors)
+                .map(|error| error.with_snippet(&sentence))
+                .collect();
+            Example { sentence, errors }
+        })
+        .collect()
+}
+
+pub fn check(source: &str, locale: Locale) -> Result<Report, String> {
+    let grammar = parse_ebnf(source)?;
+    let productions = to_bnf(&grammar);
+    let sets = compute_sets(&productions, &grammar.rules[0].name);
+    // En el reporte solo aparecen las reglas del archivo, no los auxiliares
+    let named = |map: &BTreeMap<String, BTreeSet<String>>, empty: bool| -> BTreeMap<String, Vec<String>> {
+        grammar
+            .rules
+            .iter()
+            .map(|rule| {
+                let mut tokens: Vec<String> = map.get(&rule.name).map(|set| set.iter().cloned().collect()).unwrap_or_default();
+                if empty && sets.nullable.contains(&rule.name) {
+                    tokens.push(EMPTY.to_string());
+                }
+                (rule.name.clone(), tokens)
+            })
+            .collect()
+    };
+    Ok(Report {
+        first: named(&sets.first, true),
+        follow: named(&sets.follow, false),
+        conflicts: find_conflicts(&productions, &sets),
+        problems: find_problems(&grammar, &productions),
+        examples: check_examples(&grammar, &productions, locale),
+    })
+}
+
+// Reglas como JSON para dibujar diagramas de sintaxis
+pub fn to_railroad(source: &str) -> Result<String, String> {
+    serde_json::to_string_pretty(&parse_ebnf(source)?.rules).map_err(|e| e.to_string())
+}
+
+// Resumen del chequeo para la terminal
+pub fn render(report: &Report) -> String {
+    let mut text = String::from("FIRST\n");
+    for (rule, tokens) in &report.first {
+        text.push_str(&format!("    {:<24} {}\n", rule, tokens.join(" ")));
+    }
+    text.push_str("FOLLOW\n");
+    for (rule, tokens) in &report.follow {
+        text.push_str(&format!("    {:<24} {}\n", rule, tokens.join(" ")));
+    }
+    for conflict in &report.conflicts {
+        let resolved = if conflict.resolved { " (resuelto en parse.rs)" } else { "" };
+        text.push_str(&format!("Conflicto {} en '{}': {}{}\n", conflict.kind, conflict.rule, conflict.tokens.join(" "), resolved));
+    }
+    for problem in &report.problems {
+        text.push_str(&format!("{}\n", problem));
+    }
+    let rejected: Vec<&Example> = report.examples.iter().filter(|example| !example.errors.is_empty()).collect();
+    text.push_str(&format!("Oraciones de ejemplo aceptadas: {}/{}\n", report.examples.len() - rejected.len(), report.examples.len()));
+    for example in rejected {
+        text.push_str(&format!("Rechazada: {}\n", example.sentence));
+        for error in &example.errors {
+            text.push_str(&format!("{}\n", error.render()));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // grammar.ebnf y parse.rs deben describir el mismo lenguaje
+    #[test]
+    fn grammar_matches_parser() {
+        let report = check(SOURCE, Locale::Es).unwrap();
+        assert!(report.is_ok(), "{}", render(&report));
+    }
+
+    #[test]
+    fn parser_accepts_generated_sentences() {
+        let report = check(SOURCE, Locale::Es).unwrap();
+        assert!(!report.examples.is_empty());
+        for example in &report.examples {
+            let (result, lexical) = parse::parse_source(&example.sentence, &dialect::ENGLISH, Locale::Es);
+            assert!(lexical.is_empty(), "errores léxicos en {:?}: {:?}", example.sentence, lexical);
+            assert!(result.errors.is_empty(), "errores de sintaxis en {:?}: {:?}", example.sentence, result.errors);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Serialize, Deserialize};
+
+// Idioma de los mensajes de diagnóstico. Se elige en cada petición desde la configuración
+// del front-end, así que dos usuarios del mismo binario pueden recibir idiomas distintos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    Es,
+    En,
+}
+
+impl Locale {
+    // Sin idioma se usa español, el idioma original de los mensajes
+    pub fn from_name(name: Option<&str>) -> Result<Locale, String> {
+        match name.map(|name| name.to_lowercase()).as_deref() {
+            None | Some("es") | Some("spanish") | Some("español") => Ok(Locale::Es),
+            Some("en") | Some("english") => Ok(Locale::En),
+            Some(other) => Err(format!("Idioma desconocido: {}", other)),
+        }
+    }
+}
+
+// Texto del catálogo con su traducción a cada idioma
+#[derive(Debug, Clone, Copy)]
+pub struct Text {
+    pub es: &'static str,
+    pub en: &'static str,
+}
+
+impl Text {
+    pub fn get(&self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::Es => self.es,
+            Locale::En => self.en,
+        }
+    }
+
+    // Traducción con los marcadores {nombre} sustituidos por sus valores. Se recorre la
+    // plantilla una sola vez para que un valor como '{' no se confunda con un marcador.
+    pub fn fill(&self, locale: Locale, args: &[(&str, &str)]) -> String {
+        let mut text = String::new();
+        let mut rest = self.get(locale);
+        while let Some(open) = rest.find('{') {
+            text.push_str(&rest[..open]);
+            let after_open = &rest[open + 1..];
+            match after_open.find('}').and_then(|close| {
+                let name = &after_open[..close];
+                args.iter().find(|(arg, _)| *arg == name).map(|(_, value)| (close, *value))
+            }) {
+                Some((close, value)) => {
+                    text.push_str(value);
+                    rest = &after_open[close + 1..];
+                }
+                None => {
+                    text.push('{');
+                    rest = after_open;
+                }
+            }
+        }
+        text.push_str(rest);
+        text
+    }
+}
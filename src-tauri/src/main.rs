@@ -4,64 +4,217 @@
 use std::fs;
 use std::io::Write; // Importa el trait Write
 use std::sync::Mutex;
-use crate::globals::TokenType;
-use crate::globals::StateType;
-use crate::globals::NodeType;
+use crate::globals::Token;
+use crate::parse::TreeNode;
+use crate::document::{Document, TextEdit, TokenDelta};
+use crate::dialect::Dialect;
+use crate::dfa::TokenTrace;
+use crate::diagnostic::Diagnostic;
+use crate::codes::Explanation;
+use crate::locale::Locale;
+use crate::cst::{ConcreteNode, SyntaxNode};
+use crate::ast::Program;
+use crate::format::FormatOptions;
+use crate::semantic::{Analysis, SemanticOptions};
 
 mod globals;
 mod scan;
 mod parse;
-
-impl TreeNode {
-    fn new(node_type: NodeType) -> Self {
-        TreeNode {
-            node_type,
-            token: None,
-            value: None,
-            children: Vec::new(),
-        }
-    }
-}
+mod document;
+mod dialect;
+mod dfa;
+mod diagnostic;
+mod codes;
+mod locale;
+mod ast;
+mod cst;
+mod format;
+mod export;
+mod grammar;
+mod semantic;
+mod fold;
+mod cfg;
+mod structure;
+mod cli;
 
 #[macro_use]
 extern crate lazy_static;
 
 lazy_static! {
-    static ref ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static ref DOCUMENT: Mutex<Document> = Mutex::new(Document::new(String::new(), dialect::ENGLISH.clone()));
+}
+
+// Resuelve el dialecto elegido para el proyecto; sin dialecto se usan las palabras reservadas en inglés
+fn load_dialect(dialect: Option<String>) -> Result<Dialect, String> {
+    match dialect {
+        Some(name_or_path) => Dialect::load(&name_or_path),
+        None => Ok(dialect::ENGLISH.clone()),
+    }
 }
 
 #[tauri::command]
-fn lexic(content: String) -> Result<(Vec<(TokenType, String, usize, usize)>, Vec<(TokenType, String, usize, usize)>), String> {
-    Ok(scan::get_token(&content))
+fn lexic(content: String, dialect: Option<String>) -> Result<(Vec<Token>, Vec<Token>), String> {
+    let dialect = load_dialect(dialect)?;
+    Ok(scan::get_token(&content, &dialect))
 }
 
+// Exporta la tabla de transiciones del DFA del escáner como Graphviz DOT o JSON
 #[tauri::command]
-fn parse(tokens: Vec<(TokenType, String, usize, usize)>) -> Result<(TreeNode, Vec<String>), String> {
-    let mut errors_str: Vec<String> = Vec::new();
-    let mut current_token = 0;
-    let syntax_tree = match parse::parse_program(&tokens, &mut current_token, &mut errors_str) {
-        Ok(tree) => tree,
-        Err(err) => {
-            parse::log_error(err.to_string());
-            TreeNode::new(NodeType::Error)
-        }
-    };
-    let errors_from_global = {
-        let mut errors = ERRORS.lock().unwrap();
-        let errors_clone = errors.clone();
-        errors.clear(); // Clear errors after retrieval
-        errors_clone
-    };
+fn scanner_dfa(format: String) -> Result<String, String> {
+    match format.as_str() {
+        "dot" => Ok(dfa::to_dot()),
+        "json" => dfa::to_json(),
+        _ => Err(format!("Formato desconocido: {}", format)),
+    }
+}
 
-    // Combine local and global errors
-    errors_str.extend(errors_from_global.iter().cloned());
-    Ok((syntax_tree, errors_str))
+#[tauri::command]
+fn trace_tokens(content: String, dialect: Option<String>) -> Result<Vec<TokenTrace>, String> {
+    let dialect = load_dialect(dialect)?;
+    Ok(dfa::trace(&content, &dialect))
+}
+
+#[tauri::command]
+fn open_document(content: String, dialect: Option<String>) -> Result<(Vec<Token>, Vec<Token>), String> {
+    let dialect = load_dialect(dialect)?;
+    let mut document = DOCUMENT.lock().unwrap();
+    *document = Document::new(content, dialect);
+    Ok((document.tokens().to_vec(), document.errors().to_vec()))
+}
+
+#[tauri::command]
+fn edit_document(edits: Vec<TextEdit>) -> Result<Vec<TokenDelta>, String> {
+    let mut document = DOCUMENT.lock().unwrap();
+    Ok(edits.iter().map(|edit| document.apply_edit(edit)).collect())
+}
+
+// Errores léxicos con su código y, si se pasa el texto del programa, la línea señalada
+#[tauri::command]
+fn lexical_diagnostics(errors: Vec<Token>, content: Option<String>, locale: Option<String>) -> Result<Vec<Diagnostic>, String> {
+    let locale = Locale::from_name(locale.as_deref())?;
+    Ok(errors
+        .iter()
+        .map(|error| {
+            let diagnostic = Diagnostic::lexical(error, locale);
+            match &content {
+                Some(content) => diagnostic.with_snippet(content),
+                None => diagnostic,
+            }
+        })
+        .collect())
+}
+
+// Con el texto del programa, cada error de sintaxis trae la línea señalada con '^'
+#[tauri::command]
+fn parse(tokens: Vec<Token>, content: Option<String>, dialect: Option<String>, locale: Option<String>) -> Result<(TreeNode, Vec<Diagnostic>), String> {
+    let dialect = load_dialect(dialect)?;
+    let locale = Locale::from_name(locale.as_deref())?;
+    let result = parse::parse(&tokens, content.as_deref(), &dialect, locale);
+    let mut errors = result.errors;
+    if let Some(content) = content {
+        errors = errors.into_iter().map(|error| error.with_snippet(&content)).collect();
+    }
+    Ok((cst::tree_view(&SyntaxNode::new_root(result.green)), errors))
+}
+
+// Árbol tipado (ast.rs) en JSON, para herramientas que prefieren no interpretar el TreeNode.
+// Con `fold` las subexpresiones constantes se reemplazan por su valor (fold.rs).
+#[tauri::command]
+fn typed_ast(tokens: Vec<Token>, dialect: Option<String>, fold: Option<bool>) -> Result<Program, String> {
+    let dialect = load_dialect(dialect)?;
+    let ast = parse::parse(&tokens, None, &dialect, Locale::Es).ast;
+    Ok(if fold.unwrap_or(false) { fold::fold_program(&ast) } else { ast })
+}
+
+// Árbol concreto completo (tokens, espacios y comentarios) con el rango de cada elemento
+#[tauri::command]
+fn syntax_tree(content: String, dialect: Option<String>) -> Result<ConcreteNode, String> {
+    let dialect = load_dialect(dialect)?;
+    let (result, _) = parse::parse_source(&content, &dialect, Locale::Es);
+    let root = SyntaxNode::new_root(result.green);
+    debug_assert_eq!(root.text(), content, "el CST debe reproducir el texto del programa");
+    Ok(cst::dump(&root))
+}
+
+// Tabla de símbolos, árbol anotado y errores semánticos del programa (semantic.rs)
+#[tauri::command]
+fn semantic(content: String, dialect: Option<String>, options: Option<SemanticOptions>, locale: Option<String>) -> Result<Analysis, String> {
+    let dialect = load_dialect(dialect)?;
+    let locale = Locale::from_name(locale.as_deref())?;
+    let (result, lexical) = parse::parse_source(&content, &dialect, locale);
+    let mut analysis = semantic::analyze(&result.ast, &options.unwrap_or_default(), locale);
+    analysis.tree = Some(semantic::annotate(&SyntaxNode::new_root(result.green), &analysis.types));
+    // Con errores léxicos el análisis se hizo sobre los tokens que sí se reconocieron; se
+    // muestran junto a los semánticos para que se vea por qué faltan partes del programa
+    let mut diagnostics: Vec<Diagnostic> = lexical
+        .iter()
+        .map(|error| Diagnostic::lexical(error, locale))
+        .chain(analysis.diagnostics)
+        .map(|error| error.with_snippet(&content))
+        .collect();
+    diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    analysis.diagnostics = diagnostics;
+    Ok(analysis)
+}
+
+// Árbol sintáctico como Graphviz DOT ("dot"), expresión S ("sexpr") o texto con sangría ("text")
+#[tauri::command]
+fn export_tree(content: String, dialect: Option<String>, format: String) -> Result<String, String> {
+    let dialect = load_dialect(dialect)?;
+    let (result, _) = parse::parse_source(&content, &dialect, Locale::Es);
+    export::export(&cst::tree_view(&SyntaxNode::new_root(result.green)), &format)
+}
+
+// Grafo de flujo de control del programa (cfg.rs) como Graphviz DOT ("dot") o JSON ("json")
+#[tauri::command]
+fn control_flow(content: String, dialect: Option<String>, format: String) -> Result<String, String> {
+    let dialect = load_dialect(dialect)?;
+    let (result, _) = parse::parse_source(&content, &dialect, Locale::Es);
+    cfg::export(&cfg::build(&result.ast), &format)
+}
+
+// Gramática del lenguaje: el texto EBNF ("ebnf"), las reglas para diagramas de sintaxis
+// ("railroad") o el chequeo de FIRST/FOLLOW, conflictos LL(1) y oraciones de ejemplo ("check")
+#[tauri::command]
+fn grammar(format: String, locale: Option<String>) -> Result<String, String> {
+    let locale = Locale::from_name(locale.as_deref())?;
+    match format.as_str() {
+        "ebnf" => Ok(grammar::SOURCE.to_string()),
+        "railroad" => grammar::to_railroad(grammar::SOURCE),
+        "check" => serde_json::to_string_pretty(&grammar::check(grammar::SOURCE, locale)?).map_err(|e| e.to_string()),
+        _ => Err(format!("Formato desconocido: {}", format)),
+    }
+}
+
+// Programa con el estilo canónico (format.rs). Con errores no se formatea: se devuelven
+// los errores con su línea señalada
+#[tauri::command]
+fn format(content: String, dialect: Option<String>, options: Option<FormatOptions>, locale: Option<String>) -> Result<String, String> {
+    let dialect = load_dialect(dialect)?;
+    let locale = Locale::from_name(locale.as_deref())?;
+    format::format(&content, &dialect, &options.unwrap_or_default(), locale).map_err(|errors| {
+        let errors: Vec<String> = errors.iter().map(Diagnostic::render).collect();
+        format!("{}\n{}", format::NOT_FORMATTED.get(locale), errors.join("\n"))
+    })
+}
+
+#[tauri::command]
+fn explain_error(code: String, locale: Option<String>) -> Result<Explanation, String> {
+    let locale = Locale::from_name(locale.as_deref())?;
+    match codes::explain(&code) {
+        Some(entry) => Ok(entry.localize(locale)),
+        None => Err(format!("Código de error desconocido: {}", code)),
+    }
 }
 
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(status) = cli::run(&args) {
+        std::process::exit(status);
+    }
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![save_file, remove_file, lexic, parse])
+        .invoke_handler(tauri::generate_handler![save_file, remove_file, lexic, scanner_dfa, trace_tokens, open_document, edit_document, lexical_diagnostics, parse, typed_ast, syntax_tree, semantic, export_tree, control_flow, grammar, format, explain_error])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1,578 +1,844 @@
-use std::sync::Mutex;
+use serde::{Serialize, Deserialize};
 use crate::globals::TokenType;
 use crate::globals::NodeType;
-
-#[macro_use]
-extern crate lazy_static;
-
-mod globals;
-
-
-lazy_static! {
-    static ref ERRORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
-}
+use crate::globals::Token;
+use crate::dialect::Dialect;
+use crate::diagnostic::{self, Diagnostic};
+use crate::codes;
+use crate::locale::{Locale, Text};
+use crate::ast::{BinOp, Expr, Ident, Program, Span, Stmt, VarType};
+use crate::cst::{self, GreenBuilder, GreenNode, TokenKind, Trivia};
+use crate::scan::Scanner;
+use std::collections::VecDeque;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct TreeNode {
-    node_type: NodeType,
-    token: Option<TokenType>,
-    value: Option<String>,
-    children: Vec<TreeNode>,
+pub struct TreeNode {
+    pub node_type: NodeType,
+    pub token: Option<TokenType>,
+    pub value: Option<String>,
+    pub children: Vec<TreeNode>,
 }
 
-pub fn log_error(error: String) {
-    let mut errors = ERRORS.lock().unwrap();
-    if !errors.contains(&error) {
-        errors.push(error);
+impl TreeNode {
+    pub fn new(node_type: NodeType) -> Self {
+        TreeNode {
+            node_type,
+            token: None,
+            value: None,
+            children: Vec::new(),
+        }
     }
-}
 
-fn match_token(tokens: &[(TokenType, String, usize, usize)], expected: TokenType, current_token: &mut usize) -> Result<(), String> {
-    if *current_token < tokens.len() && tokens[*current_token].0 == expected {
-        *current_token += 1;
-        Ok(())
-    } else {
-        println!("token in match: {:?}", tokens.get(*current_token));
-        Err(format!("Error de sintaxis: se esperaba {:?} en la posición {:?}", expected, tokens.get(*current_token)))
+    // Hoja Factor con el token y su lexema
+    pub fn leaf(token: TokenType, value: &str) -> Self {
+        TreeNode {
+            node_type: NodeType::Factor,
+            token: Some(token),
+            value: Some(value.to_string()),
+            children: Vec::new(),
+        }
     }
 }
 
-pub fn parse_program(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize, errors: &mut Vec<String>) -> Result<TreeNode, String> {
-    let mut root = TreeNode::new(NodeType::MainRoot);
-    while *current_token < tokens.len() && tokens[*current_token].0 != TokenType::ENDFILE {
-        match parse_statement(tokens, current_token) {
-            Ok(statement_node) => root.children.push(statement_node),
-            Err(err) => errors.push(err.to_string()), // Convertir el error en una cadena antes de agregarlo al vector
-        }
+// Tokens con los que empieza una sentencia; junto con ';', '}' y el fin de archivo forman
+// el conjunto de sincronización del modo pánico
+const STATEMENT_START: [TokenType; 12] = [
+    TokenType::IF,
+    TokenType::WHILE,
+    TokenType::WRITE,
+    TokenType::READ,
+    TokenType::DO,
+    TokenType::REPEAT,
+    TokenType::RETURN,
+    TokenType::CIN,
+    TokenType::COUT,
+    TokenType::MAIN,
+    TokenType::INTEGER,
+    TokenType::DOUBLE,
+];
+
+const EXPRESSION_OPERATORS: [TokenType; 10] = [
+    TokenType::PLUS,
+    TokenType::MINUS,
+    TokenType::LT,
+    TokenType::LTE,
+    TokenType::GT,
+    TokenType::GTE,
+    TokenType::EQ,
+    TokenType::NEQ,
+    TokenType::AND,
+    TokenType::OR,
+];
+
+const TERM_OPERATORS: [TokenType; 4] = [TokenType::TIMES, TokenType::DIVIDE, TokenType::MODULO, TokenType::POWER];
+
+const FACTOR_START: [TokenType; 4] = [TokenType::NumInt, TokenType::NumReal, TokenType::ID, TokenType::LPAREN];
+
+// Código del catálogo para un token obligatorio que no apareció
+fn missing_token_code(expected: &TokenType) -> &'static str {
+    match expected {
+        TokenType::SEMICOLON => codes::MISSING_SEMICOLON,
+        TokenType::LPAREN | TokenType::RPAREN => codes::MISSING_PAREN,
+        TokenType::LBRACE | TokenType::RBRACE => codes::MISSING_BRACE,
+        TokenType::ASSIGN | TokenType::INCREMENT | TokenType::DECREMENT => codes::MISSING_ASSIGN,
+        TokenType::ID => codes::MISSING_IDENTIFIER,
+        _ => codes::MISSING_KEYWORD,
     }
+}
 
-    Ok(root)
+// Los tokens se piden a la fuente a medida que hacen falta: el parser solo guarda el token
+// actual, el siguiente (para distinguir `x = ...` de una expresión) y el último consumido.
+struct Parser<'a, I: Iterator<Item = Token>> {
+    source: I,
+    lookahead: VecDeque<Token>,
+    previous: Option<Token>,
+    // Número de tokens consumidos
+    current: usize,
+    dialect: &'a Dialect,
+    locale: Locale,
+    errors: Vec<Diagnostic>,
+    // Tokens que se probaron en la posición actual; forman el conjunto esperado si hay un error
+    expected: Vec<TokenType>,
+    // Modo pánico: tras un error se suprimen los siguientes hasta reconocer de nuevo un token esperado
+    recovering: bool,
+    // Árbol concreto que se arma a la par del árbol tipado, con la trivia antes de cada token
+    builder: GreenBuilder,
+    trivia: Option<Trivia<'a>>,
+    trivia_flushed: usize,
 }
-fn parse_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    match tokens.get(*current_token) {
-        Some((TokenType::ID, _, _, _)) => {
-            if let Some((TokenType::INCREMENT, _, _, _)) = tokens.get(*current_token + 1) {
-                return parse_increment_statement(tokens, current_token);
-            } else if let Some((TokenType::DECREMENT, _, _, _)) = tokens.get(*current_token + 1) {
-                return parse_decrement_statement(tokens, current_token);
-            }
-        }
-        _ => {}
-    }
-
-    match tokens.get(*current_token) {
-        Some((TokenType::COLON, _, _, _)) => {
-            *current_token+=1;
-            return Err("Error de sintaxis: token fuera de un case ':'".to_string());
-        }
-        _ => {}
-    }
-
-
-    match tokens.get(*current_token) {
-        Some((TokenType::IF, _, _, _)) => return parse_if_statement(tokens, current_token),
-        Some((TokenType::WHILE, _, _, _)) => return parse_while_statement(tokens, current_token),
-        Some((TokenType::WRITE, _, _, _)) => return parse_write_statement(tokens, current_token),
-        Some((TokenType::READ, _, _, _)) => return parse_read_statement(tokens, current_token),
-        Some((TokenType::DO, _, _, _)) => return parse_do_while_statement(tokens, current_token),
-        Some((TokenType::REPEAT, _, _, _)) => return parse_repeat_until_statement(tokens, current_token),
-        Some((TokenType::RETURN, _, _, _)) => return parse_return_statement(tokens, current_token),
-        Some((TokenType::CIN, _, _, _)) => return parse_cin_statement(tokens, current_token),
-        Some((TokenType::COUT, _, _, _)) => return parse_cout_statement(tokens, current_token),
-        Some((TokenType::MAIN, _, _, _)) => return parse_main_function(tokens, current_token),
-        Some((TokenType::INTEGER, _, _, _)) => return parse_int_variable_declaration(tokens, current_token),
-        Some((TokenType::DOUBLE, _, _, _)) => return parse_double_variable_declaration(tokens, current_token),
-        Some((TokenType::ID, _, _, _)) => {
-            let assignment_node = parse_assignment(tokens, current_token)?;
-            if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-                *current_token += 1;
-                return Ok(assignment_node);
-            } else {
-                return Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token));
+
+impl<'a, I: Iterator<Item = Token>> Parser<'a, I> {
+    // Completa la ventana de dos tokens desde la fuente
+    fn fill(&mut self) {
+        while self.lookahead.len() < 2 {
+            match self.source.next() {
+                Some(token) => self.lookahead.push_back(token),
+                None => break,
             }
         }
-        _ => if is_part_of_expression(tokens, current_token) {
-            println!("token: {:?}",tokens.get(*current_token));
-            return Err(format!("Error de sintaxis: se esperaba una asignación a un identificador antes de la posición {:?}", tokens.get(*current_token)));
-        } else {
-            return Err(format!("Error de sintaxis: token inesperado {:?}", tokens.get(*current_token)));
-        },
     }
-}
 
-fn is_part_of_expression(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> bool {
-    if parse_expression(tokens, current_token).is_ok() {
-        return true;
+    fn token_type(&self, offset: usize) -> Option<&TokenType> {
+        self.lookahead.get(offset).map(|token| &token.0)
     }
-    false
-}
 
+    fn at(&self, expected: &TokenType) -> bool {
+        self.token_type(0) == Some(expected)
+    }
 
-fn parse_int_variable_declaration(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::IntStatement);
-
-    // Parsear la palabra clave 'int'
-    match_token(tokens, TokenType::INTEGER, current_token)?;
-
-    // Parsear los identificadores
-    loop {
-        match tokens.get(*current_token) {
-            Some((TokenType::ID, id, _, _)) => {
-                node.children.push(TreeNode {
-                    node_type: NodeType::Factor,
-                    token: Some(TokenType::ID),
-                    value: Some(id.clone()),
-                    children: Vec::new(),
-                });
-                *current_token += 1;
-                if let Some((TokenType::COMMA, _, _, _)) = tokens.get(*current_token) {
-                    *current_token += 1; // Avanzar si hay una coma
-                } else {
-                    break; // Salir del bucle si no hay más identificadores
-                }
-            }
-            _ => return Err(format!("Error de sintaxis: se esperaba un identificador en la posición {:?}", tokens.get(*current_token))),
+    fn at_end(&self) -> bool {
+        matches!(self.token_type(0), None | Some(TokenType::ENDFILE))
+    }
+
+    // Como `at`, pero registra el token en el conjunto esperado de la posición actual
+    fn check(&mut self, expected: &TokenType) -> bool {
+        if !self.expected.contains(expected) {
+            self.expected.push(expected.clone());
         }
+        self.at(expected)
     }
 
-    // Verificar si hay un punto y coma al final
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1; // Avanzar si hay un punto y coma
-        Ok(node)
-    } else {
-        Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token))
+    fn check_any(&mut self, expected: &[TokenType]) -> bool {
+        let mut found = false;
+        for token in expected {
+            found |= self.check(token);
+        }
+        found
     }
-}
 
-fn parse_double_variable_declaration(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::DoubleStatement);
-
-    match_token(tokens, TokenType::DOUBLE, current_token)?;
-    loop {
-        match tokens.get(*current_token) {
-            Some((TokenType::ID, id, _, _)) => {
-                node.children.push(TreeNode {
-                    node_type: NodeType::Factor,
-                    token: Some(TokenType::ID),
-                    value: Some(id.clone()),
-                    children: Vec::new(),
-                });
-                *current_token += 1;
-                if let Some((TokenType::COMMA, _, _, _)) = tokens.get(*current_token) {
-                    *current_token += 1; // Avanzar si hay una coma
-                } else {
-                    break; // Salir del bucle si no hay más identificadores
+    fn advance(&mut self) {
+        self.flush_trivia();
+        if let Some(token) = self.lookahead.pop_front() {
+            self.builder.token(TokenKind::Token(token.0.clone()), cst::token_text(&token));
+            self.previous = Some(token);
+            self.current += 1;
+            self.fill();
+        }
+        self.expected.clear();
+    }
+
+    // Agrega al CST la trivia que precede al token actual. Se hace antes de abrir un nodo
+    // para que los nodos empiecen en su primer token y no en los espacios anteriores.
+    fn flush_trivia(&mut self) {
+        if self.trivia_flushed <= self.current {
+            if let (Some(trivia), Some(token)) = (self.trivia.as_mut(), self.lookahead.front()) {
+                for piece in trivia.before(token) {
+                    self.builder.token(piece.kind, &piece.text);
                 }
             }
-            _ => return Err(format!("Error de sintaxis: se esperaba un identificador en la posición {:?}", tokens.get(*current_token))),
+            self.trivia_flushed = self.current + 1;
         }
     }
 
-    // Verificar si hay un punto y coma al final
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1; // Avanzar si hay un punto y coma
-        Ok(node)
-    } else {
-        Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token))
+    fn start_node(&mut self, kind: NodeType) {
+        self.flush_trivia();
+        self.builder.start_node(kind);
     }
-}
 
+    fn checkpoint(&mut self) -> usize {
+        self.flush_trivia();
+        self.builder.checkpoint()
+    }
 
-fn parse_if_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::IfStatement);
-    match_token(tokens, TokenType::IF, current_token)?;
-    let condition_node = parse_expression(tokens, current_token)?;
-    node.children.push(condition_node);
-    if  let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
+    fn finish_node(&mut self) {
+        self.builder.finish_node();
     }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
-        }
-        Err(err) => {
-            log_error(err.to_string());
+
+    // Registra el error `code` del catálogo en el token actual; `args` llena los marcadores del mensaje
+    fn log_error(&mut self, code: &str, args: &[(&str, &str)]) {
+        if !self.recovering {
+            let token = self.lookahead.front().or(self.previous.as_ref());
+            self.errors.push(Diagnostic::new(code, self.locale, token, args));
+            self.recovering = true;
         }
     }
-    if  let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    if let Some((TokenType::ELSE, _, _, _)) = tokens.get(*current_token) {
-        let else_node = parse_else_statement(tokens, current_token);
-        match else_node {
-            Ok(else_node) => {
-                node.children.push(else_node);
+
+    // Alternativas del conjunto esperado escritas como en el código; los grupos completos
+    // (sentencias, expresiones, operadores) se nombran en lugar de listar cada token
+    fn expected_alternatives(&self) -> Vec<String> {
+        let mut statement_start = STATEMENT_START.to_vec();
+        statement_start.push(TokenType::ID);
+        let groups: [(&[TokenType], Text); 3] = [
+            (&statement_start, diagnostic::A_STATEMENT),
+            (&FACTOR_START, diagnostic::AN_EXPRESSION),
+            (&[EXPRESSION_OPERATORS.as_slice(), TERM_OPERATORS.as_slice()].concat(), diagnostic::AN_OPERATOR),
+        ];
+        let mut grouped: Vec<&TokenType> = Vec::new();
+        let mut labels = Vec::new();
+        for (members, label) in groups.iter() {
+            if members.iter().all(|token| self.expected.contains(token) && !grouped.contains(&token)) {
+                grouped.extend(members.iter());
+                labels.push(label.get(self.locale).to_string());
             }
-            Err(err) => {
-                log_error(err.to_string());
+        }
+        let mut alternatives: Vec<String> = Vec::new();
+        for token in self.expected.iter().filter(|token| !grouped.contains(token)) {
+            let spelling = diagnostic::spelling(token, self.dialect, self.locale);
+            if !alternatives.contains(&spelling) {
+                alternatives.push(spelling);
             }
         }
+        alternatives.extend(labels);
+        alternatives
     }
-    Ok(node)
-}
 
-fn parse_else_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::ElseStatement);
-    match_token(tokens, TokenType::ELSE, current_token)?;
-    if  let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
+    // Reporta que el token actual no está en el conjunto esperado
+    fn error_expected(&mut self, code: &str) {
+        if self.recovering {
+            return;
         }
-        Err(err) => {
-            log_error(err.to_string());
+        let expected = self.expected_alternatives();
+        let found = self.lookahead.front().cloned();
+        let after = match &self.previous {
+            Some((_, lexeme, _, _)) => diagnostic::AFTER.fill(self.locale, &[("lexeme", lexeme)]),
+            None => String::new(),
+        };
+        self.log_error(code, &[
+            ("expected", &diagnostic::join_alternatives(&expected, self.locale)),
+            ("after", &after),
+            ("found", &diagnostic::found(found.as_ref(), self.locale)),
+        ]);
+        if let Some(error) = self.errors.last_mut() {
+            error.expected = expected;
+            error.found = found.map(|token| token.1.clone());
         }
     }
-    if  let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    Ok(node)
-}
 
-
-fn parse_do_while_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::DoWhileStatement);
-    match_token(tokens, TokenType::DO, current_token)?;
-    if  let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
-        }
-        Err(err) => {
-            log_error(err.to_string());
+    // Si el token actual es el esperado lo consume; si no, reporta el error y actúa como si
+    // el token hubiera estado presente (inserción virtual) sin avanzar
+    fn match_token(&mut self, expected: TokenType) -> bool {
+        if self.check(&expected) {
+            self.advance();
+            self.recovering = false;
+            true
+        } else {
+            self.error_expected(missing_token_code(&expected));
+            false
         }
     }
-    if  let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
-    }
-    if  let Err(err) = match_token(tokens, globals:TokenType::WHILE, current_token) {
-        log_error(err.to_string());
-    }
-    let condition_node = parse_expression(tokens, current_token)?;
-    node.children.push(condition_node);
-    if let Some((globals:TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1;
-    } else {
-        return Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token));
+
+    fn match_identifier(&mut self) -> Option<Ident> {
+        if self.check(&TokenType::ID) {
+            let token = &self.lookahead[0];
+            let ident = Ident { name: token.1.clone(), span: Span::of(token) };
+            self.start_node(NodeType::Factor);
+            self.advance();
+            self.finish_node();
+            self.recovering = false;
+            Some(ident)
+        } else {
+            self.error_expected(codes::MISSING_IDENTIFIER);
+            None
+        }
     }
-    Ok(node)
-}
 
+    // Para sentencias que ya se eligieron por empezar con un identificador; si aun así faltara,
+    // se reporta y se usa un identificador vacío
+    fn expect_identifier(&mut self) -> Ident {
+        let span = self.span();
+        self.match_identifier().unwrap_or(Ident { name: String::new(), span })
+    }
 
-fn parse_while_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::WhileStatement);
-    match_token(tokens, TokenType::WHILE, current_token)?;
-    let condition_node = parse_expression(tokens, current_token)?;
-    node.children.push(condition_node);
-    if  let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
+    fn at_statement_start(&self) -> bool {
+        match self.token_type(0) {
+            Some(TokenType::ID) => matches!(
+                self.token_type(1),
+                Some(TokenType::ASSIGN) | Some(TokenType::INCREMENT) | Some(TokenType::DECREMENT)
+            ),
+            Some(token) => STATEMENT_START.contains(token),
+            None => false,
+        }
     }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
+
+    // Descarta tokens hasta un punto seguro: después de un ';', antes de '}', del inicio de
+    // otra sentencia o del fin de archivo. Los bloques '{ ... }' se saltan completos, salvo
+    // que dentro aparezca una palabra que empieza una sentencia (`main`, un tipo, `if`...):
+    // así un '{' de más no hace perder el resto del programa.
+    fn synchronize(&mut self) {
+        while !self.at_end() && !self.at(&TokenType::RBRACE) && !self.at_statement_start() {
+            if self.at(&TokenType::SEMICOLON) {
+                self.advance();
+                return;
+            }
+            if self.at(&TokenType::LBRACE) {
+                let mut depth = 0;
+                while !self.at_end() {
+                    match self.token_type(0) {
+                        Some(TokenType::LBRACE) => depth += 1,
+                        Some(TokenType::RBRACE) => depth -= 1,
+                        _ => {}
+                    }
+                    self.advance();
+                    if depth == 0 || self.token_type(0).map_or(false, |token| STATEMENT_START.contains(token)) {
+                        break;
+                    }
+                }
+                continue;
+            }
+            self.advance();
         }
-        Err(err) => {
-            log_error(err.to_string());
+    }
+
+    fn parse_program(&mut self) -> Program {
+        let mut items = Vec::new();
+        // La raíz se abre antes de la trivia inicial para que cubra todo el texto
+        self.builder.start_node(NodeType::MainRoot);
+        self.parse_statement_list(&mut items, false);
+        // El fin de archivo y la trivia final también forman parte del CST
+        while !self.lookahead.is_empty() {
+            self.advance();
         }
+        self.finish_node();
+        Program { items }
     }
-    if  let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
+
+    // Posición del token actual, o del último si ya no quedan
+    fn span(&self) -> Span {
+        match self.lookahead.front().or(self.previous.as_ref()) {
+            Some(token) => Span::of(token),
+            None => Span { line: 1, column: 1 },
+        }
     }
-    Ok(node)
-}
 
-fn parse_repeat_until_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::RepeatUntilStatement);
-    match_token(tokens, TokenType::REPEAT, current_token)?;
-    if  let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
+    // Sentencias hasta el '}' del bloque (o hasta el fin de archivo en el nivel superior).
+    // Cada vuelta avanza al menos un token, así que el ciclo siempre termina.
+    fn parse_statement_list(&mut self, statements: &mut Vec<Stmt>, in_block: bool) {
+        while !(self.at_end() || in_block && self.check(&TokenType::RBRACE)) {
+            let start = self.current;
+            if let Some(statement) = self.parse_statement() {
+                statements.push(statement);
+            }
+            if self.current == start {
+                self.error_expected(codes::UNEXPECTED_TOKEN);
+                self.advance();
+            }
+        }
     }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
+
+    fn parse_block(&mut self) -> Vec<Stmt> {
+        let mut statements = Vec::new();
+        if self.match_token(TokenType::LBRACE) {
+            self.parse_statement_list(&mut statements, true);
+            self.match_token(TokenType::RBRACE);
+        } else if self.at_statement_start() {
+            // Sin '{' el cuerpo se toma como una sola sentencia, así no se exige después un '}'
+            statements.extend(self.parse_statement());
         }
-        Err(err) => {
-            log_error(err.to_string());
+        statements
+    }
+
+    // Cuerpo de if, else, while, do y repeat: un bloque o una sola sentencia sin llaves.
+    // Así `else if` es un else cuyo cuerpo es otro IfStatement.
+    fn parse_body(&mut self) -> Vec<Stmt> {
+        let braced = self.check(&TokenType::LBRACE);
+        self.check_any(&STATEMENT_START);
+        self.check(&TokenType::ID);
+        if !braced && self.at_statement_start() {
+            self.parse_statement().into_iter().collect()
+        } else {
+            self.parse_block()
         }
     }
-    if  let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
+
+    fn parse_statement(&mut self) -> Option<Stmt> {
+        self.check_any(&STATEMENT_START);
+        self.check(&TokenType::ID);
+        let statement = match self.token_type(0) {
+            Some(TokenType::ID) => match self.token_type(1) {
+                Some(TokenType::INCREMENT) => self.parse_increment_statement(),
+                Some(TokenType::DECREMENT) => self.parse_decrement_statement(),
+                _ => self.parse_assignment(),
+            },
+            Some(TokenType::IF) => self.parse_if_statement(),
+            Some(TokenType::WHILE) => self.parse_while_statement(),
+            Some(TokenType::WRITE) => self.parse_write_statement(),
+            Some(TokenType::READ) => self.parse_read_statement(),
+            Some(TokenType::DO) => self.parse_do_while_statement(),
+            Some(TokenType::REPEAT) => self.parse_repeat_until_statement(),
+            Some(TokenType::RETURN) => self.parse_return_statement(),
+            Some(TokenType::CIN) => self.parse_cin_statement(),
+            Some(TokenType::COUT) => self.parse_cout_statement(),
+            Some(TokenType::MAIN) => self.parse_main_function(),
+            Some(TokenType::INTEGER) => self.parse_int_variable_declaration(),
+            Some(TokenType::DOUBLE) => self.parse_double_variable_declaration(),
+            Some(TokenType::COLON) => {
+                self.log_error(codes::COLON_OUTSIDE_CASE, &[]);
+                self.advance();
+                return None;
+            }
+            Some(TokenType::NumInt) | Some(TokenType::NumReal) | Some(TokenType::LPAREN) => {
+                let found = diagnostic::found(self.lookahead.front(), self.locale);
+                self.log_error(codes::ASSIGNMENT_TARGET, &[("found", &found)]);
+                self.synchronize();
+                return None;
+            }
+            _ => {
+                self.error_expected(codes::UNEXPECTED_TOKEN);
+                self.synchronize();
+                return None;
+            }
+        };
+        // Si la sentencia terminó con un error sin resolver, se descarta el resto hasta un punto seguro
+        if self.recovering {
+            self.synchronize();
+        }
+        Some(statement)
     }
-    if  let Err(err) = match_token(tokens, TokenType::UNTIL, current_token) {
-        log_error(err.to_string());
+
+    fn parse_variable_declaration(&mut self, ty: VarType, keyword: TokenType) -> Stmt {
+        let span = self.span();
+        let mut names = Vec::new();
+        self.start_node(match ty {
+            VarType::Int => NodeType::IntStatement,
+            VarType::Double => NodeType::DoubleStatement,
+        });
+        self.match_token(keyword);
+        // Parsear los identificadores
+        while let Some(name) = self.match_identifier() {
+            names.push(name);
+            if !self.check(&TokenType::COMMA) {
+                break; // Salir del bucle si no hay más identificadores
+            }
+            self.advance(); // Avanzar si hay una coma
+        }
+        self.match_token(TokenType::SEMICOLON);
+        self.finish_node();
+        Stmt::Declaration { ty, names, span }
     }
-    let condition_node = parse_expression(tokens, current_token)?;
-    node.children.push(condition_node);
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1;
-    } else {
-        return Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token));
+
+    fn parse_int_variable_declaration(&mut self) -> Stmt {
+        self.parse_variable_declaration(VarType::Int, TokenType::INTEGER)
     }
-    Ok(node)
-}
 
-fn parse_main_function(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::MainFunction);
-    match_token(tokens, TokenType::MAIN, current_token)?;
-    if  let Err(err) = match_token(tokens, TokenType::LPAREN, current_token) {
-        log_error(err.to_string());
+    fn parse_double_variable_declaration(&mut self) -> Stmt {
+        self.parse_variable_declaration(VarType::Double, TokenType::DOUBLE)
     }
-    if  let Err(err) = match_token(tokens, TokenType::RPAREN, current_token) {
-        log_error(err.to_string());
+
+    fn parse_if_statement(&mut self) -> Stmt {
+        let span = self.span();
+        self.start_node(NodeType::IfStatement);
+        self.match_token(TokenType::IF);
+        let cond = self.parse_expression();
+        let then = self.parse_body();
+        // El else se asocia al if más cercano: si el cuerpo era otro if sin llaves, ese if
+        // ya consumió el else
+        let else_ = if self.check(&TokenType::ELSE) {
+            Some(self.parse_else_statement())
+        } else {
+            None
+        };
+        self.finish_node();
+        Stmt::If { cond, then, else_, span }
+    }
+
+    fn parse_else_statement(&mut self) -> Vec<Stmt> {
+        self.start_node(NodeType::ElseStatement);
+        self.match_token(TokenType::ELSE);
+        let body = self.parse_body();
+        self.finish_node();
+        body
+    }
+
+    fn parse_do_while_statement(&mut self) -> Stmt {
+        let span = self.span();
+        self.start_node(NodeType::DoWhileStatement);
+        self.match_token(TokenType::DO);
+        let body = self.parse_body();
+        self.match_token(TokenType::WHILE);
+        let cond = self.parse_expression();
+        self.match_token(TokenType::SEMICOLON);
+        self.finish_node();
+        Stmt::DoWhile { body, cond, span }
+    }
+
+    fn parse_while_statement(&mut self) -> Stmt {
+        let span = self.span();
+        self.start_node(NodeType::WhileStatement);
+        self.match_token(TokenType::WHILE);
+        let cond = self.parse_expression();
+        let body = self.parse_body();
+        self.finish_node();
+        Stmt::While { cond, body, span }
+    }
+
+    fn parse_repeat_until_statement(&mut self) -> Stmt {
+        let span = self.span();
+        self.start_node(NodeType::RepeatUntilStatement);
+        self.match_token(TokenType::REPEAT);
+        let body = self.parse_body();
+        self.match_token(TokenType::UNTIL);
+        let cond = self.parse_expression();
+        self.match_token(TokenType::SEMICOLON);
+        self.finish_node();
+        Stmt::RepeatUntil { body, cond, span }
+    }
+
+    fn parse_main_function(&mut self) -> Stmt {
+        let span = self.span();
+        self.start_node(NodeType::MainFunction);
+        self.match_token(TokenType::MAIN);
+        self.match_token(TokenType::LPAREN);
+        self.match_token(TokenType::RPAREN);
+        let body = self.parse_block();
+        self.finish_node();
+        Stmt::Main { body, span }
+    }
+
+    // Sentencias de la forma `palabra_reservada identificador ;`
+    fn parse_identifier_statement(&mut self, node_type: NodeType, keyword: TokenType) -> (Option<Ident>, Span) {
+        let span = self.span();
+        self.start_node(node_type);
+        self.match_token(keyword);
+        let target = self.match_identifier();
+        self.match_token(TokenType::SEMICOLON);
+        self.finish_node();
+        (target, span)
+    }
+
+    fn parse_write_statement(&mut self) -> Stmt {
+        let (target, span) = self.parse_identifier_statement(NodeType::WriteStatement, TokenType::WRITE);
+        Stmt::Write { target, span }
+    }
+
+    fn parse_read_statement(&mut self) -> Stmt {
+        let (target, span) = self.parse_identifier_statement(NodeType::ReadStatement, TokenType::READ);
+        Stmt::Read { target, span }
+    }
+
+    fn parse_cin_statement(&mut self) -> Stmt {
+        let (target, span) = self.parse_identifier_statement(NodeType::CinStatement, TokenType::CIN);
+        Stmt::Cin { target, span }
+    }
+
+    fn parse_return_statement(&mut self) -> Stmt {
+        let span = self.span();
+        self.start_node(NodeType::ReturnStatement);
+        self.match_token(TokenType::RETURN);
+        let value = self.parse_expression();
+        self.match_token(TokenType::SEMICOLON);
+        self.finish_node();
+        Stmt::Return { value, span }
+    }
+
+    fn parse_cout_statement(&mut self) -> Stmt {
+        let span = self.span();
+        self.start_node(NodeType::CoutStatement);
+        self.match_token(TokenType::COUT);
+        let value = self.parse_expression();
+        self.match_token(TokenType::SEMICOLON);
+        self.finish_node();
+        Stmt::Cout { value, span }
+    }
+
+    // Sentencias de la forma `identificador ++ ;` y `identificador -- ;`. Solo se llega aquí
+    // con un identificador seguido del operador, así que ambos están presentes.
+    fn parse_step_statement(&mut self, node_type: NodeType, operator: TokenType) -> (Ident, Span) {
+        let span = self.span();
+        self.start_node(node_type);
+        let target = self.expect_identifier();
+        self.match_token(operator);
+        self.match_token(TokenType::SEMICOLON);
+        self.finish_node();
+        (target, span)
+    }
+
+    fn parse_increment_statement(&mut self) -> Stmt {
+        let (target, span) = self.parse_step_statement(NodeType::Increment, TokenType::INCREMENT);
+        Stmt::Increment { target, span }
+    }
+
+    fn parse_decrement_statement(&mut self) -> Stmt {
+        let (target, span) = self.parse_step_statement(NodeType::Decrement, TokenType::DECREMENT);
+        Stmt::Decrement { target, span }
+    }
+
+    // Operador binario del token actual si está entre `operators`. En el CST abre el nodo
+    // `kind` desde `checkpoint`, envolviendo el operando izquierdo ya analizado.
+    fn binary_operator(&mut self, operators: &[TokenType], checkpoint: usize, kind: NodeType) -> Option<(BinOp, Span)> {
+        if self.check_any(operators) {
+            let token = &self.lookahead[0];
+            let operator = BinOp::from_token(&token.0).map(|op| (op, Span::of(token)));
+            self.builder.start_node_at(checkpoint, kind);
+            self.start_node(NodeType::Factor);
+            self.advance();
+            self.finish_node();
+            operator
+        } else {
+            None
+        }
     }
-    if  let Err(err) = match_token(tokens, TokenType::LBRACE, current_token) {
-        log_error(err.to_string());
+
+    fn parse_expression(&mut self) -> Expr {
+        let checkpoint = self.checkpoint();
+        let mut expr = self.parse_term();
+        while let Some((op, span)) = self.binary_operator(&EXPRESSION_OPERATORS, checkpoint, NodeType::Expression) {
+            let right = self.parse_term();
+            self.finish_node();
+            expr = Expr::Binary { op, left: Box::new(expr), right: Box::new(right), span };
+        }
+        expr
     }
-    let statement_node = parse_statement(tokens, current_token);
-    match statement_node {
-        Ok(statement_node) => {
-            node.children.push(statement_node);
+
+    fn parse_term(&mut self) -> Expr {
+        let checkpoint = self.checkpoint();
+        let mut expr = self.parse_factor();
+        while let Some((op, span)) = self.binary_operator(&TERM_OPERATORS, checkpoint, NodeType::Term) {
+            let right = self.parse_factor();
+            self.finish_node();
+            expr = Expr::Binary { op, left: Box::new(expr), right: Box::new(right), span };
         }
-        Err(err) => {
-            log_error(err.to_string());
+        expr
+    }
+
+    fn parse_factor(&mut self) -> Expr {
+        self.check_any(&FACTOR_START);
+        let span = self.span();
+        match self.lookahead.front() {
+            Some((token @ (TokenType::NumInt | TokenType::NumReal | TokenType::ID), value, _, _)) => {
+                let expr = match token {
+                    TokenType::NumInt => Expr::Int { value: value.clone(), span },
+                    TokenType::NumReal => Expr::Real { value: value.clone(), span },
+                    _ => Expr::Var(Ident { name: value.clone(), span }),
+                };
+                self.start_node(NodeType::Factor);
+                self.advance();
+                self.finish_node();
+                self.recovering = false;
+                expr
+            }
+            Some((TokenType::LPAREN, _, _, _)) => {
+                self.start_node(NodeType::Factor);
+                self.advance();
+                let inner = self.parse_expression();
+                self.match_token(TokenType::RPAREN);
+                self.finish_node();
+                Expr::Group { inner: Box::new(inner), span }
+            }
+            _ => {
+                // Falta el operando: se inserta virtualmente un nodo de error sin consumir el token
+                self.error_expected(codes::MISSING_OPERAND);
+                self.start_node(NodeType::Error);
+                self.finish_node();
+                Expr::Error { span }
+            }
         }
     }
-    if  let Err(err) = match_token(tokens, TokenType::RBRACE, current_token) {
-        log_error(err.to_string());
+
+    fn parse_assignment(&mut self) -> Stmt {
+        let span = self.span();
+        self.start_node(NodeType::Assignment);
+        let target = self.expect_identifier();
+        // Sin '=' no se sabe qué quiso escribirse (`whle (x > 0)`, `x 1;`): se descarta el resto
+        // de la sentencia en lugar de leer una expresión que daría más errores
+        if !self.match_token(TokenType::ASSIGN) {
+            self.synchronize();
+            self.finish_node();
+            return Stmt::Assign { target, value: Expr::Error { span }, span };
+        }
+        let value = self.parse_expression();
+        self.match_token(TokenType::SEMICOLON);
+        self.finish_node();
+        Stmt::Assign { target, value, span }
     }
-    Ok(node)
 }
 
-fn parse_write_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::WriteStatement);
-    match_token(tokens, TokenType::WRITE, current_token)?;
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
-        *current_token += 1;
-    } else {
-        return Err(format!("Error de sintaxis: se esperaba un identificador en la posición {:?}", tokens.get(*current_token)));
-    }
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1;
-        Ok(node)
-    } else {
-        Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token))
-    }
+// Resultado del análisis: el árbol tipado, el árbol concreto sin pérdida y los errores
+pub struct Parse {
+    pub ast: Program,
+    pub green: Arc<GreenNode>,
+    pub errors: Vec<Diagnostic>,
 }
 
-fn parse_read_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::ReadStatement);
-    match_token(tokens, TokenType::READ, current_token)?;
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
-        *current_token += 1;
-    } else {
-        return Err(format!("Error de sintaxis: se esperaba un identificador en la posición {:?}", tokens.get(*current_token)));
-    }
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1;
-        Ok(node)
-    } else {
-        Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token))
-    }
+// Analiza el programa completo. Nunca se detiene en el primer error: se recupera en modo
+// pánico y reporta un solo error por cada punto donde la entrada deja de ser válida.
+// Con el texto del programa el CST incluye la trivia y su texto es idéntico a `source`.
+pub fn parse(tokens: &[Token], source: Option<&str>, dialect: &Dialect, locale: Locale) -> Parse {
+    parse_stream(tokens.iter().cloned(), source, dialect, locale)
 }
 
-fn parse_return_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::ReturnStatement);
-    match_token(tokens, TokenType::RETURN, current_token)?;
-    let expression_node = parse_expression(tokens, current_token)?;
-    node.children.push(expression_node);
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1;
-    } else {
-        return Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token));
-    }
-    Ok(node)
+// Escanea y analiza `source` a la vez: el parser pide cada token al escáner cuando lo
+// necesita, sin armar antes la lista completa. Devuelve también los errores léxicos.
+pub fn parse_source(source: &str, dialect: &Dialect, locale: Locale) -> (Parse, Vec<Token>) {
+    let mut scanner = Scanner::new(source).with_dialect(dialect);
+    let result = parse_stream(scanner.by_ref(), Some(source), dialect, locale);
+    (result, scanner.into_errors())
 }
 
-fn parse_cin_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::CinStatement);
-    match_token(tokens, TokenType::CIN, current_token)?;
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
-        *current_token += 1;
-    } else {
-        return Err(format!("Error de sintaxis: se esperaba un identificador en la posición {:?}", tokens.get(*current_token)));
-    }
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1;
-        Ok(node)
-    } else {
-        Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token))
+fn parse_stream<I: Iterator<Item = Token>>(tokens: I, source: Option<&str>, dialect: &Dialect, locale: Locale) -> Parse {
+    let mut parser = Parser {
+        source: tokens,
+        lookahead: VecDeque::new(),
+        previous: None,
+        current: 0,
+        dialect,
+        locale,
+        errors: Vec::new(),
+        expected: Vec::new(),
+        recovering: false,
+        builder: GreenBuilder::default(),
+        trivia: source.map(Trivia::new),
+        trivia_flushed: 0,
+    };
+    parser.fill();
+    let ast = parser.parse_program();
+    Parse {
+        ast,
+        green: parser.builder.finish(),
+        errors: parser.errors,
     }
 }
 
-fn parse_cout_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::CoutStatement);
-    match_token(tokens, TokenType::COUT, current_token)?;
-    let expression_node = parse_expression(tokens, current_token)?;
-    node.children.push(expression_node);
-    if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-        *current_token += 1;
-    } else {
-        return Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token));
-    }
-    Ok(node)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::ENGLISH;
+    use crate::scan::get_token;
 
-fn parse_increment_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::Increment);
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
-        *current_token += 2;
-        if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-            *current_token += 1;
-        } else {
-            return Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token));
-        }
-        Ok(node)
-    } else {
-        Err(format!("Error de sintaxis: se esperaba un identificador en la posición {:?}", tokens.get(*current_token)))
+    // Código, línea y columna de cada error de sintaxis
+    fn errors(source: &str) -> Vec<(String, usize, usize)> {
+        let (result, _) = parse_source(source, &ENGLISH, Locale::Es);
+        result.errors.into_iter().map(|error| (error.code, error.line, error.column)).collect()
     }
-}
 
-fn parse_decrement_statement(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::Decrement);
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
-        *current_token += 2;
-        if let Some((TokenType::SEMICOLON, _, _, _)) = tokens.get(*current_token) {
-            *current_token += 1;
-        } else {
-            return Err(format!("Error de sintaxis: se esperaba ';' en la posición {:?}", *current_token));
-        }
-        Ok(node)
-    } else {
-        Err(format!("Error de sintaxis: se esperaba un identificador en la posición {:?}", tokens.get(*current_token)))
+    fn error(code: &str, line: usize, column: usize) -> (String, usize, usize) {
+        (code.to_string(), line, column)
     }
-}
 
+    #[test]
+    fn one_diagnostic_per_typo() {
+        assert_eq!(errors("main() {\n    int x;\n    whle (x > 0) { x--; }\n    cout x;\n}\n"), [error(codes::MISSING_ASSIGN, 3, 10)]);
+        assert_eq!(errors("main() {\n    int x;\n    x = 1 +* 2;\n    cout x;\n}\n"), [error(codes::MISSING_OPERAND, 3, 12)]);
+        assert_eq!(errors("main() {\n    int x;\n    x = (1 + 2;\n    cout x;\n}\n"), [error(codes::MISSING_PAREN, 3, 15)]);
+    }
 
-fn parse_expression(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = parse_term(tokens, current_token)?;
-    while let Some((token, value, _, _)) = tokens.get(*current_token) {
-        match token {
-            TokenType::PLUS | TokenType::MINUS | TokenType::LT | TokenType::LTE | TokenType::GT | TokenType::GTE | TokenType::EQ | TokenType::NEQ | TokenType::AND | TokenType::OR => {
-                *current_token += 1;
-                let term_node = parse_term(tokens, current_token)?;
-                let mut expression_node = TreeNode::new(NodeType::Expression);
-                expression_node.children.push(node);
-                expression_node.children.push(TreeNode {
-                    node_type: NodeType::Factor,
-                    token: Some(token.clone()),
-                    value: Some(value.clone()),
-                    children: Vec::new(),
-                });
-                expression_node.children.push(term_node);
-                node = expression_node;
-            }
-            _ => break,
+    #[test]
+    fn missing_semicolon() {
+        assert_eq!(errors("main() {\n    int x;\n    x = 1\n    cout x;\n}\n"), [error(codes::MISSING_SEMICOLON, 4, 5)]);
+        assert_eq!(errors("main() {\n    int x\n    x = 1;\n}\n"), [error(codes::MISSING_SEMICOLON, 3, 5)]);
+    }
+
+    #[test]
+    fn stray_braces() {
+        assert_eq!(errors("main() {\n    int x;\n}\n}\n"), [error(codes::UNEXPECTED_TOKEN, 4, 1)]);
+        // El bloque que empieza con el '{' de más no se descarta: main sigue en el programa
+        let source = "{ { { main() {\n    int x;\n}\n";
+        assert_eq!(errors(source), [error(codes::UNEXPECTED_TOKEN, 1, 1)]);
+        let (result, _) = parse_source(source, &ENGLISH, Locale::Es);
+        assert!(matches!(result.ast.items.as_slice(), [Stmt::Main { .. }]));
+        assert_eq!(errors("main() {\n    int x;\n    {{ x = 1; }\n    cout x;\n}\n"), [error(codes::UNEXPECTED_TOKEN, 3, 5)]);
+    }
+
+    // Sentencias del cuerpo de main, sin errores de sintaxis
+    fn main_body(body: &str) -> Vec<Stmt> {
+        let source = format!("main() {{\n    int x;\n    {}\n}}\n", body);
+        let (result, _) = parse_source(&source, &ENGLISH, Locale::Es);
+        assert!(result.errors.is_empty(), "{:?}: {:?}", source, result.errors);
+        match result.ast.items.as_slice() {
+            [Stmt::Main { body, .. }] => body[1..].to_vec(),
+            items => panic!("{:?}", items),
         }
     }
-    Ok(node)
-}
 
-fn parse_term(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = parse_factor(tokens, current_token)?;
-    while let Some((token, value, _, _)) = tokens.get(*current_token) {
-        match token {
-            TokenType::TIMES | TokenType::DIVIDE | TokenType::MODULO | TokenType::POWER => {
-                *current_token += 1;
-                let factor_node = parse_factor(tokens, current_token)?;
-                let mut term_node = TreeNode::new(NodeType::Term);
-                term_node.children.push(node);
-                term_node.children.push(TreeNode {
-                    node_type: NodeType::Factor,
-                    token: Some(token.clone()),
-                    value: Some(value.clone()),
-                    children: Vec::new(),
-                });
-                term_node.children.push(factor_node);
-                node = term_node;
+    #[test]
+    fn else_if_chain_nests_in_else() {
+        let body = main_body("if (x == 1) { x = 2; } else if (x == 2) { x = 3; } else { x = 1; }");
+        let (then, else_) = match body.as_slice() {
+            [Stmt::If { then, else_: Some(else_), .. }] => (then, else_),
+            other => panic!("{:?}", other),
+        };
+        assert!(matches!(then.as_slice(), [Stmt::Assign { .. }]));
+        match else_.as_slice() {
+            [Stmt::If { then, else_: Some(last), .. }] => {
+                assert!(matches!(then.as_slice(), [Stmt::Assign { .. }]));
+                assert!(matches!(last.as_slice(), [Stmt::Assign { .. }]));
             }
-            _ => break,
+            other => panic!("{:?}", other),
         }
     }
-    Ok(node)
-}
 
-fn parse_factor(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    if let Some((token, value, _, _)) = tokens.get(*current_token) {
-        let mut node = TreeNode::new(NodeType::Factor);
-        match token {
-            TokenType::NumInt | TokenType::NumReal | TokenType::ID => {
-                node.token = Some(token.clone());
-                node.value = Some(value.clone());
-                *current_token += 1;
-                Ok(node)
+    // El else sin llaves pertenece al if más cercano
+    #[test]
+    fn dangling_else_binds_to_inner_if() {
+        let body = main_body("if (x > 0) if (x > 1) x = 1; else x = 2;");
+        match body.as_slice() {
+            [Stmt::If { then, else_: None, .. }] => {
+                assert!(matches!(then.as_slice(), [Stmt::If { else_: Some(_), .. }]));
             }
-            TokenType::LPAREN => {
-                *current_token += 1;
-                let expression_node = parse_expression(tokens, current_token)?;
-                if  let Err(err) = match_token(tokens, TokenType::RPAREN, current_token) {
-                    log_error(err.to_string());
-                }
-                node.children.push(expression_node);
-                Ok(node)
-            }
-            _ => Err(format!("Error de sintaxis: token inesperado {:?}", tokens.get(*current_token))),
+            other => panic!("{:?}", other),
         }
-    } else {
-        Err(format!("Error de sintaxis: token inesperado en la posición {:?}", tokens.get(*current_token)))
     }
-}
 
-fn parse_assignment(tokens: &[(TokenType, String, usize, usize)], current_token: &mut usize) -> Result<TreeNode, String> {
-    let mut node = TreeNode::new(NodeType::Assignment);
-    if let Some((TokenType::ID, id, _, _)) = tokens.get(*current_token) {
-        node.children.push(TreeNode {
-            node_type: NodeType::Factor,
-            token: Some(TokenType::ID),
-            value: Some(id.clone()),
-            children: Vec::new(),
-        });
-        *current_token += 1;
-        if  let Err(err) = match_token(tokens, TokenType::ASSIGN, current_token) {
-            log_error(err.to_string());
-        }
-        let expression_node = parse_expression(tokens, current_token)?;
-        node.children.push(expression_node);
-        Ok(node)
-    } else {
-        Err(format!("Error de sintaxis: se esperaba un identificador en la posición {:?}", tokens.get(*current_token)))
+    #[test]
+    fn braceless_loop_bodies() {
+        assert!(matches!(main_body("while (x > 0) x--;").as_slice(), [Stmt::While { body, .. }] if matches!(body.as_slice(), [Stmt::Decrement { .. }])));
+        assert!(matches!(main_body("do x++; while (x < 3);").as_slice(), [Stmt::DoWhile { body, .. }] if matches!(body.as_slice(), [Stmt::Increment { .. }])));
+        assert!(matches!(main_body("repeat x--; until (x == 0);").as_slice(), [Stmt::RepeatUntil { body, .. }] if matches!(body.as_slice(), [Stmt::Decrement { .. }])));
+        // Solo la primera sentencia forma parte del cuerpo
+        assert!(matches!(main_body("while (x > 0) x--; cout x;").as_slice(), [Stmt::While { .. }, Stmt::Cout { .. }]));
+    }
+
+    // Tokens que ninguna regla consume: el parser avanza igual y termina
+    #[test]
+    fn always_makes_progress() {
+        for source in [") ) : : else ; main() { }", "main() { ) ) ) }", "main() { x = ; ; = ; }", "} } }", "( ( ( (", "else else"] {
+            let (result, _) = parse_source(source, &ENGLISH, Locale::Es);
+            assert!(!result.errors.is_empty(), "{:?}", source);
+            assert!(result.errors.len() <= 3, "{:?}: {:?}", source, result.errors);
+        }
+    }
+
+    // Analizar pidiendo los tokens al escáner da lo mismo que analizar la lista completa
+    #[test]
+    fn streaming_matches_collected_tokens() {
+        for source in ["main() {\n    int x; // c\n    x = (1 + 2) * 3;\n    cout x;\n}\n", "main() { x = 1 @ ; if ( { }", "", "/*"] {
+            let (tokens, errors) = get_token(source, &ENGLISH);
+            let collected = parse(&tokens, Some(source), &ENGLISH, Locale::Es);
+            let (streamed, lexical) = parse_source(source, &ENGLISH, Locale::Es);
+            assert_eq!(lexical, errors);
+            assert_eq!(streamed.green, collected.green);
+            assert_eq!(streamed.ast, collected.ast);
+            assert_eq!(format!("{:?}", streamed.errors), format!("{:?}", collected.errors));
+        }
     }
 }
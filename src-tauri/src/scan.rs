@@ -1,249 +1,260 @@
+use std::str::Chars;
 use crate::globals::TokenType;
 use crate::globals::StateType;
-use crate::globals::NodeType;
-mod globals;
-
-// Función para obtener el siguiente carácter no en blanco de la línea actual
-fn get_next_char(line: &str, linepos: &mut usize, bufsize: usize) -> char {
-    if *linepos >= bufsize {
-        '\0' // Devuelve un carácter nulo al final de la línea
-    } else {
-        let c = line.chars().nth(*linepos).unwrap_or('\0'); // Usa unwrap_or para devolver un carácter nulo si el índice está fuera de rango
-        *linepos += 1;
-        c
-    }
+use crate::globals::Token;
+use crate::dialect::{Dialect, ENGLISH};
+use crate::dfa::{self, Action, CharClass, Emit};
+
+// Escáner perezoso: produce un token a la vez recorriendo el DFA desde el estado Start.
+// Los errores léxicos no se entregan como tokens, se acumulan en `errors`. Para mirar el
+// siguiente token sin consumirlo se usa `peekable()`, como con cualquier iterador.
+pub struct Scanner<'a> {
+    chars: Chars<'a>,
+    dialect: &'a Dialect,
+    lineno: usize,
+    column: usize,
+    errors: Vec<Token>,
+    finished: bool,
+    trace: Option<Vec<StateType>>,
 }
 
-// Función para retroceder un carácter en la línea actual
-fn unget_next_char(linepos: &mut usize) {
-    if *linepos > 0 {
-        *linepos -= 1;
+impl<'a> Scanner<'a> {
+    pub fn new(content: &'a str) -> Self {
+        Scanner::starting_at(content, 1, 1)
     }
-}
 
-// Función para buscar palabras reservadas y devolver su TokenType correspondiente
-fn reserved_lookup(s: &str) -> TokenType {
-    match s {
-        "if" => TokenType::IF,
-        "else" => TokenType::ELSE,
-        "do" => TokenType::DO,
-        "while" => TokenType::WHILE,
-        "repeat" => TokenType::REPEAT,
-        "until" => TokenType::UNTIL,
-        "read" => TokenType::READ,
-        "write" => TokenType::WRITE,
-        "int" => TokenType::INTEGER,
-        "double" => TokenType::DOUBLE,
-        "main" => TokenType::MAIN,
-        "return" => TokenType::RETURN,
-        "/*" => TokenType::InMultipleComment,
-        "cin" => TokenType::CIN,
-        "cout" => TokenType::COUT,
-        _ => TokenType::ID,
+    // Escáner sobre un fragmento del documento que empieza en la línea y columna dadas.
+    // El fragmento debe comenzar en una frontera segura (estado Start), p. ej. el inicio de un token.
+    pub fn starting_at(content: &'a str, lineno: usize, column: usize) -> Self {
+        Scanner {
+            chars: content.chars(),
+            dialect: &ENGLISH,
+            lineno,
+            column,
+            errors: Vec::new(),
+            finished: false,
+            trace: None,
+        }
     }
-}
 
-// Función para realizar el análisis léxico y devolver los tokens
-pub fn get_token(content: &str) -> (Vec<(TokenType, String, usize, usize)>, Vec<(TokenType, String, usize, usize)>) {
-    let mut tokens = Vec::new();
-    let mut errors = Vec::new();
-    let mut lineno = 1;
-    let mut state = StateType::Start;
-    let mut token_string = String::new();
-    let mut linepos = 0;
-    let bufsize = content.len();
-    let mut column_number = 0;
-    while linepos <= bufsize {
-        let c = get_next_char(content, &mut linepos, bufsize);
-        match state {
-            StateType::Start => {
-                if c == '\n' {
-                    lineno += 1;
-                    column_number = 1;
-                }
-                if c.is_whitespace() {
-                    // Ignorar espacios en blanco
-                    column_number +=1;
-                } else if c.is_ascii_alphabetic() || c == '_' {
-                    state = StateType::InId;
-                    token_string.push(c);
-                    column_number +=1;
-                } else if c.is_digit(10) {
-                    state = StateType::InNum;
-                    token_string.push(c);
-                    column_number +=1;
-                } else if c == '/' {
-                    let next_char = get_next_char(content, &mut linepos, bufsize);
-                    if next_char == '/' {
-                        let next_char = get_next_char(content, &mut linepos, bufsize);
-                        if next_char == '\n' {
-                            lineno += 1;
-                        } else {
-                            unget_next_char(&mut linepos);
-                            state = StateType::InComment;
-                            lineno += 1;
-                        }
-                    } else if next_char == '*' {
-                        lineno += 1;
-                        let next_char = get_next_char(content, &mut linepos, bufsize);
-                        if next_char == '\n' {
-                            lineno += 1;
-                        } else {
-                            unget_next_char(&mut linepos);
-                            state = StateType::InMultiComment;
-                            lineno += 1;
-                        }
-                    } else {
-                        tokens.push((TokenType::DIVIDE, "/".to_string(), lineno, column_number - 1));
-                        unget_next_char(&mut linepos)
-                    }
-                } else {
-                    match c {
-                        '=' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '=' {
-                                tokens.push((TokenType::EQ, "==".to_string(), lineno, column_number - 1));
-                            } else {
-                                tokens.push((TokenType::ASSIGN, "=".to_string(), lineno, column_number - 1));
-                                unget_next_char(&mut linepos);
-
-                            }
-                        }
-                        '!' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '=' {
-                                tokens.push((TokenType::NEQ, "!=".to_string(), lineno, column_number - 1));
-                            } else {
-                                errors.push((TokenType::ERROR, "!".to_string(), lineno, column_number - 1));
-                                unget_next_char(&mut linepos);
-
-                            }
-                        }
-                        '<' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '=' {
-                                tokens.push((TokenType::LTE, "<=".to_string(), lineno, column_number - 1));
-                            } else {
-                                tokens.push((TokenType::LT, "<".to_string(), lineno, column_number - 1));
-                                unget_next_char(&mut linepos);
-
-                            }
-                        }
-                        '>' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '=' {
-                                tokens.push((TokenType::GTE, ">=".to_string(), lineno, column_number - 1));
-                            } else {
-                                tokens.push((TokenType::GT, ">".to_string(), lineno, column_number - 1));
-                                unget_next_char(&mut linepos);
-
-                            }
-                        }
-                        '+' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '+' {
-                                tokens.push((TokenType::INCREMENT, "++".to_string(), lineno, column_number - 1));
-                            } else {
-                                tokens.push((TokenType::PLUS, "+".to_string(), lineno, column_number - 1));
-                                unget_next_char(&mut linepos);
-
-                            }
-                        }
-                        '-' => {
-                            let next_char = get_next_char(content, &mut linepos, bufsize);
-                            if next_char == '-' {
-                                tokens.push((TokenType::DECREMENT, "--".to_string(), lineno, column_number - 1));
-                            } else {
-                                tokens.push((TokenType::MINUS, "-".to_string(), lineno, column_number - 1));
-                                unget_next_char(&mut linepos);
-
-                            }
-                        }
-                        '*' => tokens.push((TokenType::TIMES, "*".to_string(), lineno, column_number)),
-                        '%' => tokens.push((TokenType::MODULO, "%".to_string(), lineno, column_number)),
-                        '^' => tokens.push((TokenType::POWER, "^".to_string(), lineno, column_number)),
-                        '(' => tokens.push((TokenType::LPAREN, "(".to_string(), lineno, column_number)),
-                        ')' => tokens.push((TokenType::RPAREN, ")".to_string(), lineno, column_number)),
-                        '{' => tokens.push((TokenType::LBRACE, "{".to_string(), lineno, column_number)),
-                        '}' => tokens.push((TokenType::RBRACE, "}".to_string(), lineno, column_number)),
-                        ',' => tokens.push((TokenType::COMMA, ",".to_string(), lineno, column_number)),
-                        ';' => tokens.push((TokenType::SEMICOLON, ";".to_string(), lineno, column_number)),
-                        '&' => tokens.push((TokenType::AND, "&".to_string(), lineno, column_number)),
-                        '|' => tokens.push((TokenType::OR, "|".to_string(), lineno, column_number)),
-                        ':' => tokens.push((TokenType::COLON, ":".to_string(), lineno, column_number)),
-                        '\0' => {
-                            state = StateType::EndFile;
-                        }
-                        _ => errors.push((TokenType::ERROR, c.to_string(), lineno, column_number - 1)),
-                    }
-                }
+    // Usa la tabla de palabras reservadas de otro dialecto (por defecto, inglés)
+    pub fn with_dialect(mut self, dialect: &'a Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    // Registra los estados del DFA visitados para cada token (ver dfa::trace)
+    pub fn with_trace(mut self) -> Self {
+        self.trace = Some(Vec::new());
+        self
+    }
+
+    // Estados visitados desde el token anterior hasta el último devuelto
+    pub fn take_trace(&mut self) -> Vec<StateType> {
+        self.trace.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    // Errores léxicos encontrados hasta el momento
+    pub fn errors(&self) -> &[Token] {
+        &self.errors
+    }
+
+    pub fn into_errors(self) -> Vec<Token> {
+        self.errors
+    }
+
+    // Consume el siguiente carácter actualizando la línea y la columna
+    fn get_next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.lineno += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    // Mira el siguiente carácter sin consumirlo
+    fn peek_char(&self) -> Option<char> {
+        self.chars.clone().next()
+    }
+
+    // Recorre la tabla de transiciones del DFA (dfa.rs) desde Start hasta Done
+    fn next_token(&mut self) -> Option<Token> {
+        if self.finished {
+            return None;
+        }
+        let mut state = StateType::Start;
+        let mut current_token = TokenType::ERROR;
+        let mut token_string = String::new();
+        let mut lineno = self.lineno;
+        let mut column_number = self.column;
+        // Posición del '/*' que abrió el comentario múltiple y nivel de anidamiento
+        let mut comment_start = (0, 0);
+        let mut comment_depth = 0;
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(StateType::Start);
+        }
+        while state != StateType::Done {
+            if state == StateType::Start {
+                lineno = self.lineno;
+                column_number = self.column;
             }
-            StateType::InId => {
-                if c.is_ascii_alphanumeric() || c == '_' {
-                    token_string.push(c);
-                } else {
-                    tokens.push((reserved_lookup(&token_string), token_string.clone(), lineno, (column_number - 1)));
-                    token_string.clear();
-                    state = StateType::Start;
-                    unget_next_char(&mut linepos); // Retornar un carácter
-                }
+            let c = self.peek_char();
+            let transition = dfa::transition(state, CharClass::of(c));
+            let mut next_state = transition.to;
+            if transition.consume {
+                self.get_next_char();
             }
-            StateType::InNum => {
-                if c.is_digit(10) {
-                    token_string.push(c);
-                } else if c == '.' {
-                    state = StateType::InReal;
-                    token_string.push(c);
-                } else {
-                    tokens.push((TokenType::NumInt, token_string.clone(), lineno, (column_number - 1)));
+            let consumed = c.filter(|_| transition.consume);
+            match &transition.action {
+                Action::Move | Action::Skip => {}
+                Action::Save => token_string.extend(consumed),
+                Action::Discard => token_string.clear(),
+                Action::OpenComment => {
                     token_string.clear();
-                    state = StateType::Start;
-                    unget_next_char(&mut linepos); // Retornar un carácter
+                    comment_start = (lineno, column_number);
+                    comment_depth = 1;
                 }
-            }
-            StateType::InReal => {
-                if c.is_digit(10) {
-                    token_string.push(c);
-                } else if token_string.ends_with('.') {
-                    errors.push((TokenType::ERROR, token_string.clone(), lineno, (column_number - 1)));
-                    token_string.clear();
-                    state = StateType::Start;
-                    unget_next_char(&mut linepos); //retornar un carácter
-                } else {
-                    tokens.push((TokenType::NumReal, token_string.clone(), lineno, (column_number - 1)));
+                Action::NestComment => comment_depth += 1,
+                Action::CloseComment => {
+                    comment_depth -= 1;
+                    if comment_depth > 0 {
+                        next_state = StateType::InMultiComment;
+                    }
+                }
+                Action::Unterminated => {
+                    // Comentario no cerrado: se reporta como error léxico en la posición del '/*' de apertura
+                    let (line, column) = comment_start;
+                    self.errors.push((TokenType::InMultipleComment, "/*".to_string(), line, column));
+                }
+                Action::Error => {
+                    token_string.extend(consumed);
+                    self.errors.push((TokenType::ERROR, token_string.clone(), lineno, column_number));
                     token_string.clear();
-                    state = StateType::Start;
-                    unget_next_char(&mut linepos); // Retornar un carácter
                 }
-            }
-            StateType::InComment => {
-                if c == '\n' || c == '\0' {
-                    state = StateType::Start;
-                    column_number = 1;
+                Action::Accept(emit) => {
+                    token_string.extend(consumed);
+                    current_token = match emit {
+                        Emit::Token(token) => token.clone(),
+                        Emit::Keyword => self.dialect.reserved_lookup(&token_string),
+                        Emit::Symbol => consumed.and_then(dfa::symbol_token).unwrap_or(TokenType::ERROR),
+                    };
                 }
             }
-            StateType::InMultiComment => {
-                if c == '*' {
-                    lineno += 1;
-                    let next_char = get_next_char(content, &mut linepos, bufsize);
-                    if next_char == '/' {
-                        state = StateType::Start;
-                        lineno += 1;
-                    } else {
-                        unget_next_char(&mut linepos)
-                    }
-                } else if c == '\0' {
-                    tokens.push((TokenType::InMultipleComment, "/*".to_string(), lineno, column_number - 1));
-                    println!("Error: '/*' Multiline comment not closed.");
-                    state = StateType::EndFile;
-                }
+            if state == StateType::EndFile {
+                lineno = self.lineno;
+                column_number = self.column;
+                token_string = "\0".to_string();
+                self.finished = true;
             }
-            StateType::EndFile => {
-                tokens.push((TokenType::ENDFILE, "\0".to_string(), lineno, column_number - 1));
-                break; // Salir del bucle while
+            state = next_state;
+            if let Some(trace) = self.trace.as_mut() {
+                trace.push(state);
             }
-            _ => (),
         }
+        Some((current_token, token_string, lineno, column_number))
+    }
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.next_token()
+    }
+}
+
+// Función para realizar el análisis léxico y devolver los tokens
+pub fn get_token(content: &str, dialect: &Dialect) -> (Vec<Token>, Vec<Token>) {
+    let mut scanner = Scanner::new(content).with_dialect(dialect);
+    let tokens = scanner.by_ref().collect();
+    (tokens, scanner.into_errors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(token_type: TokenType, lexeme: &str, line: usize, column: usize) -> Token {
+        (token_type, lexeme.to_string(), line, column)
+    }
+
+    #[test]
+    fn produces_tokens_one_at_a_time() {
+        let mut scanner = Scanner::new("x = 1;\ny++");
+        assert_eq!(scanner.next(), Some(token(TokenType::ID, "x", 1, 1)));
+        assert_eq!(scanner.next(), Some(token(TokenType::ASSIGN, "=", 1, 3)));
+        let rest: Vec<Token> = scanner.by_ref().collect();
+        assert_eq!(rest, [
+            token(TokenType::NumInt, "1", 1, 5),
+            token(TokenType::SEMICOLON, ";", 1, 6),
+            token(TokenType::ID, "y", 2, 1),
+            token(TokenType::INCREMENT, "++", 2, 2),
+            token(TokenType::ENDFILE, "\0", 2, 4),
+        ]);
+        // Después del fin de archivo el iterador ya no produce nada
+        assert_eq!(scanner.next(), None);
+    }
+
+    #[test]
+    fn peekable_does_not_consume() {
+        let mut scanner = Scanner::new("a b").peekable();
+        assert_eq!(scanner.peek(), Some(&token(TokenType::ID, "a", 1, 1)));
+        assert_eq!(scanner.next(), Some(token(TokenType::ID, "a", 1, 1)));
+        assert_eq!(scanner.next(), Some(token(TokenType::ID, "b", 1, 3)));
+    }
+
+    #[test]
+    fn matches_get_token() {
+        let source = "main() {\n    int x; // c\n    x = 2 @ 3.;\n}\n";
+        let mut scanner = Scanner::new(source);
+        let tokens: Vec<Token> = scanner.by_ref().collect();
+        assert_eq!((tokens, scanner.into_errors()), get_token(source, &ENGLISH));
+    }
+
+    #[test]
+    fn starting_at_offsets_positions() {
+        let tokens: Vec<Token> = Scanner::starting_at("y\n  z", 4, 7).collect();
+        assert_eq!(tokens, [
+            token(TokenType::ID, "y", 4, 7),
+            token(TokenType::ID, "z", 5, 3),
+            token(TokenType::ENDFILE, "\0", 5, 4),
+        ]);
+    }
+
+    #[test]
+    fn trace_records_states_per_token() {
+        let mut scanner = Scanner::new("ab==").with_trace();
+        scanner.next();
+        assert_eq!(scanner.take_trace(), [StateType::Start, StateType::InId, StateType::InId, StateType::Done]);
+        scanner.next();
+        assert_eq!(scanner.take_trace(), [StateType::Start, StateType::InAssign, StateType::Done]);
+        // Sin `with_trace` no se registra nada
+        let mut plain = Scanner::new("ab");
+        plain.next();
+        assert!(plain.take_trace().is_empty());
+    }
+
+    #[test]
+    fn unterminated_comment_is_an_error_at_its_opening() {
+        let mut scanner = Scanner::new("x;\n  /* sin cerrar\n y = 1;");
+        let tokens: Vec<Token> = scanner.by_ref().collect();
+        assert_eq!(tokens, [
+            token(TokenType::ID, "x", 1, 1),
+            token(TokenType::SEMICOLON, ";", 1, 2),
+            token(TokenType::ENDFILE, "\0", 3, 8),
+        ]);
+        assert_eq!(scanner.errors(), [token(TokenType::InMultipleComment, "/*", 2, 3)]);
+    }
+
+    #[test]
+    fn nested_comments_close_at_the_outer_level() {
+        let (tokens, errors) = get_token("/* a /* b */ c */ x", &ENGLISH);
+        assert_eq!(tokens, [token(TokenType::ID, "x", 1, 19), token(TokenType::ENDFILE, "\0", 1, 20)]);
+        assert!(errors.is_empty());
+        let (_, errors) = get_token("/* a /* b */ x", &ENGLISH);
+        assert_eq!(errors, [token(TokenType::InMultipleComment, "/*", 1, 1)]);
     }
-    (tokens, errors)
 }
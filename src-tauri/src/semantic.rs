@@ -0,0 +1,746 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use crate::globals::{NodeType, TokenType};
+use crate::cst::{SyntaxNode, TokenKind};
+use crate::export::Tree;
+use crate::fold::{self, Fault, Value};
+use crate::ast::{BinOp, Expr, Ident, Program, Span, Stmt, VarType};
+use crate::diagnostic::Diagnostic;
+use crate::codes;
+use crate::locale::{Locale, Text};
+use crate::structure;
+
+// Análisis semántico sobre el árbol tipado (ast.rs). Construye la tabla de símbolos a partir
+// de las declaraciones `int` y `double`: cada variable con su tipo, su ámbito, la línea donde
+// se declaró y las líneas donde se usa. Cada función, ciclo y rama de un `if` abre un ámbito:
+// lo que se declara dentro no se ve fuera, y puede ocultar una variable de un ámbito exterior.
+// Reporta las variables sin declarar o declaradas dos veces (errores) y las que nunca se usan
+// o nunca se leen (advertencias), y las que se pueden leer antes de que se les asigne un valor
+// en algún camino del programa. Con el mismo recorrido detecta el código inalcanzable (después
+// de un `return` o de un ciclo infinito) y los ciclos cuya condición constante es falsa desde
+// el principio.
+//
+// También calcula el tipo de cada expresión. Entre `int` y `double` el `int` se convierte a
+// `double`; las comparaciones y `&`, `|` dan `bool`, que a su vez se usa como un `int` (0 o 1).
+// Las expresiones formadas solo por literales tienen además un valor conocido (fold.rs); con
+// los tipos y los valores se arma el árbol anotado (`annotate`).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ScopeKind {
+    // Nivel superior del programa
+    Global,
+    Function,
+    // Rama de un `if` o `else`
+    Block,
+    // Cuerpo de un `while`, `do` o `repeat`; la condición queda fuera
+    Loop,
+}
+
+// Lints opcionales; el front-end los guarda en la configuración como las opciones de formato
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SemanticOptions {
+    // Advertir cuando una declaración oculta una variable de un ámbito exterior
+    pub warn_shadowing: bool,
+}
+
+impl Default for SemanticOptions {
+    fn default() -> Self {
+        SemanticOptions { warn_shadowing: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Scope {
+    pub id: usize,
+    pub kind: ScopeKind,
+    pub name: String,
+    pub parent: Option<usize>,
+    // Línea donde empieza el ámbito (0 para el global)
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub ty: VarType,
+    // Identificador del ámbito en `SymbolTable::scopes`
+    pub scope: usize,
+    pub line: usize,
+    pub column: usize,
+    // Líneas donde se lee o se escribe la variable, en orden y sin repetir
+    pub uses: Vec<usize>,
+    pub read: bool,
+    pub written: bool,
+}
+
+// Cómo usa una sentencia la variable: `x = 1` la escribe, `cout x` la lee y `x++` hace ambas cosas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+// Tipo de una expresión. `bool` no se puede declarar: es el tipo de las comparaciones y de `&` y `|`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Type {
+    Int,
+    Double,
+    Bool,
+}
+
+impl From<VarType> for Type {
+    fn from(ty: VarType) -> Self {
+        match ty {
+            VarType::Int => Type::Int,
+            VarType::Double => Type::Double,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Double => write!(f, "double"),
+            Type::Bool => write!(f, "bool"),
+        }
+    }
+}
+
+// Tipo (y valor, si es constante) de una expresión o de un identificador del árbol tipado,
+// identificado por la posición de su nodo: la del operador en las binarias y la del '(' en las
+// agrupadas
+#[derive(Debug, Clone, Serialize)]
+pub struct TypedExpr {
+    pub line: usize,
+    pub column: usize,
+    pub ty: Type,
+    pub val: Option<Value>,
+}
+
+// Copia del TreeNode (ver cst::tree_view) con el tipo y el valor de cada expresión
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnotatedNode {
+    pub node_type: NodeType,
+    pub token: Option<TokenType>,
+    pub value: Option<String>,
+    pub ty: Option<Type>,
+    pub val: Option<Value>,
+    pub children: Vec<AnnotatedNode>,
+}
+
+const FIRST_DECLARATION: Text = Text {
+    es: "primera declaración de '{name}'",
+    en: "first declaration of '{name}'",
+};
+
+const SHADOWED_DECLARATION: Text = Text {
+    es: "declaración de '{name}' que queda oculta",
+    en: "shadowed declaration of '{name}'",
+};
+
+const LEFT: Text = Text {
+    es: "izquierdo",
+    en: "left",
+};
+
+const RIGHT: Text = Text {
+    es: "derecho",
+    en: "right",
+};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SymbolTable {
+    pub scopes: Vec<Scope>,
+    pub symbols: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Analysis {
+    pub table: SymbolTable,
+    pub types: Vec<TypedExpr>,
+    pub diagnostics: Vec<Diagnostic>,
+    // Árbol anotado; lo agrega quien tiene el CST del programa (ver `annotate`)
+    pub tree: Option<AnnotatedNode>,
+}
+
+struct Analyzer {
+    locale: Locale,
+    options: SemanticOptions,
+    table: SymbolTable,
+    // Ámbitos abiertos, del global al actual
+    open_scopes: Vec<usize>,
+    types: Vec<TypedExpr>,
+    diagnostics: Vec<Diagnostic>,
+    // Variables con valor asignado en todos los caminos hasta este punto (asignación definida).
+    // None después de un `return`: ese punto no se alcanza y cualquier variable cuenta como asignada.
+    assigned: Option<HashSet<usize>>,
+    // Variables ya reportadas como leídas sin valor, para avisar una sola vez por variable
+    uninitialized: HashSet<usize>,
+}
+
+impl Analyzer {
+    fn enter_scope(&mut self, kind: ScopeKind, name: &str, line: usize) {
+        let id = self.table.scopes.len();
+        self.table.scopes.push(Scope {
+            id,
+            kind,
+            name: name.to_string(),
+            parent: self.open_scopes.last().copied(),
+            line,
+        });
+        self.open_scopes.push(id);
+    }
+
+    fn exit_scope(&mut self) {
+        self.open_scopes.pop();
+    }
+
+    fn current_scope(&self) -> usize {
+        *self.open_scopes.last().expect("siempre está abierto el ámbito global")
+    }
+
+    fn report(&mut self, code: &str, span: Span, name: &str, args: &[(&str, &str)]) {
+        let args: Vec<(&str, &str)> = std::iter::once(("name", name)).chain(args.iter().copied()).collect();
+        self.diagnostics.push(Diagnostic::at(code, self.locale, span, name.chars().count(), &args));
+    }
+
+    // Diagnóstico sobre un operador (longitud 1), con el lado del operando que lo causa
+    fn report_operand(&mut self, code: &str, span: Span, op: &str, left: bool) {
+        let side = if left { LEFT } else { RIGHT }.get(self.locale);
+        let args = [("side", side), ("operator", op)];
+        self.diagnostics.push(Diagnostic::at(code, self.locale, span, 1, &args));
+    }
+
+    // Error al evaluar una constante: en el operador, o en el literal si `op` es None
+    fn report_fault(&mut self, fault: Fault, span: Span, op: Option<BinOp>) {
+        let code = match fault {
+            Fault::DivisionByZero => codes::DIVISION_BY_ZERO,
+            Fault::Overflow => codes::INTEGER_OVERFLOW,
+            Fault::NotConstant => return,
+        };
+        let (length, operator) = match op {
+            Some(op) => (op.symbol().len(), op.symbol()),
+            None => (1, ""),
+        };
+        self.diagnostics.push(Diagnostic::at(code, self.locale, span, length, &[("operator", operator)]));
+    }
+
+    // `if`, `while` y `until` aceptan `bool` o `int`; un `double` es un error. Devuelve el valor
+    // de verdad de la condición si es constante.
+    fn condition(&mut self, cond: &Expr) -> Option<bool> {
+        let (ty, val) = self.expression(cond);
+        if ty == Some(Type::Double) {
+            let length = match cond {
+                Expr::Var(ident) => ident.name.chars().count(),
+                _ => 1,
+            };
+            self.diagnostics.push(Diagnostic::at(codes::CONDITION_NOT_BOOLEAN, self.locale, cond.start(), length, &[]));
+            return None;
+        }
+        val.map(Value::is_true)
+    }
+
+    fn declare(&mut self, ident: &Ident, ty: VarType) {
+        // Un identificador vacío es uno que el parser insertó al recuperarse de un error
+        if ident.name.is_empty() {
+            return;
+        }
+        self.record(ident.span, ty.into(), None);
+        let scope = self.current_scope();
+        if let Some(previous) = self.lookup(&ident.name) {
+            let previous = self.table.symbols[previous].clone();
+            let same_scope = previous.scope == scope;
+            let code = if same_scope { codes::REDECLARED_VARIABLE } else { codes::SHADOWED_VARIABLE };
+            if same_scope || self.options.warn_shadowing {
+                let line = previous.line.to_string();
+                let length = ident.name.chars().count();
+                let args = [("name", ident.name.as_str()), ("line", line.as_str())];
+                let note = if same_scope { FIRST_DECLARATION } else { SHADOWED_DECLARATION };
+                let span = Span { line: previous.line, column: previous.column };
+                let diagnostic = Diagnostic::at(code, self.locale, ident.span, length, &args)
+                    .with_related(note.fill(self.locale, &args), span, length);
+                self.diagnostics.push(diagnostic);
+            }
+            // Los usos siguientes se refieren a la primera declaración
+            if same_scope {
+                return;
+            }
+        }
+        // Las variables globales empiezan en 0, como en C
+        if self.table.scopes[scope].kind == ScopeKind::Global {
+            self.assign(self.table.symbols.len());
+        }
+        self.table.symbols.push(Symbol {
+            name: ident.name.clone(),
+            ty,
+            scope,
+            line: ident.span.line,
+            column: ident.span.column,
+            uses: Vec::new(),
+            read: false,
+            written: false,
+        });
+    }
+
+    // Símbolo visible con ese nombre: el del ámbito más interno que lo declare
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.open_scopes.iter().rev().find_map(|scope| {
+            self.table
+                .symbols
+                .iter()
+                .position(|symbol| symbol.scope == *scope && symbol.name == name)
+        })
+    }
+
+    fn record(&mut self, span: Span, ty: Type, val: Option<Value>) {
+        self.types.push(TypedExpr { line: span.line, column: span.column, ty, val });
+    }
+
+    // Marca el uso y devuelve el tipo declarado de la variable
+    fn use_variable(&mut self, ident: &Ident, access: Access) -> Option<Type> {
+        if ident.name.is_empty() {
+            return None;
+        }
+        let index = match self.lookup(&ident.name) {
+            Some(index) => index,
+            None => {
+                self.report(codes::UNDECLARED_VARIABLE, ident.span, &ident.name, &[]);
+                return None;
+            }
+        };
+        if access != Access::Write {
+            self.check_assigned(index, ident);
+        }
+        if access != Access::Read {
+            self.assign(index);
+        }
+        let symbol = &mut self.table.symbols[index];
+        symbol.read |= access != Access::Write;
+        symbol.written |= access != Access::Read;
+        if !symbol.uses.contains(&ident.span.line) {
+            symbol.uses.push(ident.span.line);
+            symbol.uses.sort_unstable();
+        }
+        let ty = symbol.ty.into();
+        self.record(ident.span, ty, None);
+        Some(ty)
+    }
+
+    fn assign(&mut self, index: usize) {
+        if let Some(assigned) = &mut self.assigned {
+            assigned.insert(index);
+        }
+    }
+
+    fn check_assigned(&mut self, index: usize, ident: &Ident) {
+        let assigned = self.assigned.as_ref().map_or(true, |assigned| assigned.contains(&index));
+        if !assigned && self.uninitialized.insert(index) {
+            self.report(codes::UNINITIALIZED_VARIABLE, ident.span, &ident.name, &[]);
+        }
+    }
+
+    // Variables asignadas después de cualquiera de los dos caminos: las asignadas en ambos
+    fn merge(left: Option<HashSet<usize>>, right: Option<HashSet<usize>>) -> Option<HashSet<usize>> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(left.intersection(&right).copied().collect()),
+            (None, other) | (other, None) => other,
+        }
+    }
+
+    // Advertencias que solo se conocen al terminar: variables sin usar o que nunca se leen
+    fn report_unused(&mut self) {
+        let unused: Vec<(&str, Symbol)> = self
+            .table
+            .symbols
+            .iter()
+            .filter_map(|symbol| match (symbol.uses.is_empty(), symbol.read) {
+                (true, _) => Some((codes::UNUSED_VARIABLE, symbol.clone())),
+                (false, false) => Some((codes::UNREAD_VARIABLE, symbol.clone())),
+                _ => None,
+            })
+            .collect();
+        for (code, symbol) in unused {
+            let span = Span { line: symbol.line, column: symbol.column };
+            self.report(code, span, &symbol.name, &[]);
+        }
+    }
+
+    // Solo se reporta la primera sentencia inalcanzable de cada bloque, y no dentro de un bloque
+    // que ya es inalcanzable entero
+    fn statements(&mut self, statements: &[Stmt]) {
+        let mut reported = self.assigned.is_none();
+        for statement in statements {
+            if self.assigned.is_none() && !reported {
+                reported = true;
+                self.diagnostics.push(Diagnostic::at(codes::UNREACHABLE_CODE, self.locale, statement.span(), 1, &[]));
+            }
+            self.statement(statement);
+        }
+    }
+
+    // Si el ciclo nunca termina (su condición es constante y no hay `break`), lo que le sigue es inalcanzable
+    fn after_loop(&mut self, exits: bool) {
+        if !exits {
+            self.assigned = None;
+        }
+    }
+
+    // Bloque con su propio ámbito
+    fn block(&mut self, kind: ScopeKind, name: &str, line: usize, statements: &[Stmt]) {
+        self.enter_scope(kind, name, line);
+        self.statements(statements);
+        self.exit_scope();
+    }
+
+    fn statement(&mut self, statement: &Stmt) {
+        match statement {
+            Stmt::Main { body, span } => {
+                self.enter_scope(ScopeKind::Function, "main", span.line);
+                let before = self.assigned.clone();
+                self.statements(body);
+                // Un `return` dentro de main no hace inalcanzable lo que sigue a la función
+                self.assigned = before;
+                self.exit_scope();
+            }
+            Stmt::Declaration { ty, names, .. } => {
+                for name in names {
+                    self.declare(name, *ty);
+                }
+            }
+            Stmt::Assign { target, value, .. } => {
+                let (value, _) = self.expression(value);
+                if self.use_variable(target, Access::Write) == Some(Type::Int) && value == Some(Type::Double) {
+                    self.report(codes::NARROWING_ASSIGNMENT, target.span, &target.name, &[]);
+                }
+            }
+            Stmt::If { cond, then, else_, span } => {
+                self.condition(cond);
+                let before = self.assigned.clone();
+                self.block(ScopeKind::Block, "if", span.line, then);
+                let after_then = std::mem::replace(&mut self.assigned, before);
+                if let Some(else_) = else_ {
+                    self.block(ScopeKind::Block, "else", span.line, else_);
+                }
+                let after_else = self.assigned.take();
+                self.assigned = Analyzer::merge(after_then, after_else);
+            }
+            // El cuerpo puede no ejecutarse nunca: lo que asigna no cuenta después del ciclo
+            Stmt::While { cond, body, span } => {
+                let always = self.condition(cond);
+                if always == Some(false) {
+                    self.diagnostics.push(Diagnostic::at(codes::LOOP_NEVER_RUNS, self.locale, cond.start(), 1, &[]));
+                }
+                let before = self.assigned.clone();
+                self.block(ScopeKind::Loop, "while", span.line, body);
+                self.assigned = before;
+                self.after_loop(always != Some(true));
+            }
+            Stmt::DoWhile { body, cond, span } => {
+                self.block(ScopeKind::Loop, "do", span.line, body);
+                let always = self.condition(cond);
+                self.after_loop(always != Some(true));
+            }
+            Stmt::RepeatUntil { body, cond, span } => {
+                self.block(ScopeKind::Loop, "repeat", span.line, body);
+                let always = self.condition(cond);
+                self.after_loop(always != Some(false));
+            }
+            Stmt::Read { target: Some(target), .. } | Stmt::Cin { target: Some(target), .. } => {
+                self.use_variable(target, Access::Write);
+            }
+            Stmt::Write { target: Some(target), .. } => {
+                self.use_variable(target, Access::Read);
+            }
+            Stmt::Read { target: None, .. } | Stmt::Write { target: None, .. } | Stmt::Cin { target: None, .. } => {}
+            Stmt::Cout { value, .. } => {
+                self.expression(value);
+            }
+            Stmt::Return { value, .. } => {
+                self.expression(value);
+                self.assigned = None;
+            }
+            Stmt::Increment { target, .. } | Stmt::Decrement { target, .. } => {
+                self.use_variable(target, Access::ReadWrite);
+            }
+        }
+    }
+
+    // Tipo de la expresión, o None si no se conoce (variable sin declarar u operando faltante),
+    // y su valor si es constante
+    fn expression(&mut self, expr: &Expr) -> (Option<Type>, Option<Value>) {
+        let (ty, val, span) = match expr {
+            Expr::Int { span, .. } | Expr::Real { span, .. } => {
+                let ty = if matches!(expr, Expr::Int { .. }) { Type::Int } else { Type::Double };
+                let val = match fold::literal(expr) {
+                    Some(Ok(val)) => Some(val),
+                    Some(Err(fault)) => {
+                        self.report_fault(fault, *span, None);
+                        None
+                    }
+                    None => None,
+                };
+                (Some(ty), val, *span)
+            }
+            // use_variable ya registra el tipo de la variable
+            Expr::Var(ident) => return (self.use_variable(ident, Access::Read), None),
+            Expr::Binary { op, left, right, span } => {
+                let (left_ty, left_val) = self.expression(left);
+                let (right_ty, right_val) = self.expression(right);
+                let ty = self.binary(*op, left_ty, right_ty, *span);
+                // '%' con un double ya es un error de tipos (S005); no se reporta además la división
+                let modulo_on_real = *op == BinOp::Mod && (left_ty == Some(Type::Double) || right_ty == Some(Type::Double));
+                let val = match (left_val, right_val) {
+                    (Some(left), Some(right)) => match fold::evaluate(*op, left, right) {
+                        Ok(val) => Some(val),
+                        Err(fault) => {
+                            self.report_fault(fault, *span, Some(*op));
+                            None
+                        }
+                    },
+                    // Con un divisor constante cero no hace falta conocer el dividendo: `x / 0`
+                    (None, Some(right)) if fold::divides_by_zero(*op, right) && !modulo_on_real => {
+                        self.report_fault(Fault::DivisionByZero, *span, Some(*op));
+                        None
+                    }
+                    _ => None,
+                };
+                (ty, val, *span)
+            }
+            Expr::Group { inner, span } => {
+                let (ty, val) = self.expression(inner);
+                (ty, val, *span)
+            }
+            Expr::Error { .. } => return (None, None),
+        };
+        if let Some(ty) = ty {
+            self.record(span, ty, val);
+        }
+        (ty, val)
+    }
+
+    fn binary(&mut self, op: BinOp, left: Option<Type>, right: Option<Type>, span: Span) -> Option<Type> {
+        match op {
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Pow => match (left?, right?) {
+                (Type::Double, _) | (_, Type::Double) => Some(Type::Double),
+                _ => Some(Type::Int),
+            },
+            BinOp::Mod => {
+                for (ty, is_left) in [(left, true), (right, false)] {
+                    if ty == Some(Type::Double) {
+                        self.report_operand(codes::MODULO_ON_REAL, span, "%", is_left);
+                    }
+                }
+                // Con un operando real el resultado se toma como entero para no repetir errores
+                Some(Type::Int)
+            }
+            BinOp::Lt | BinOp::Lte | BinOp::Gt | BinOp::Gte | BinOp::Eq | BinOp::Neq => Some(Type::Bool),
+            BinOp::And | BinOp::Or => {
+                for (ty, is_left) in [(left, true), (right, false)] {
+                    if ty == Some(Type::Double) {
+                        self.report_operand(codes::LOGICAL_OPERAND_NOT_BOOLEAN, span, op.symbol(), is_left);
+                    }
+                }
+                Some(Type::Bool)
+            }
+        }
+    }
+}
+
+// `Factor NumInt 3 : int = 3`, `Expression : bool`
+impl Tree for AnnotatedNode {
+    fn label(&self) -> String {
+        let mut label = match (&self.token, &self.value) {
+            (Some(token), Some(value)) => format!("{:?} {:?} {}", self.node_type, token, value),
+            _ => format!("{:?}", self.node_type),
+        };
+        if let Some(ty) = self.ty {
+            label.push_str(&format!(" : {}", ty));
+        }
+        if let Some(val) = self.val {
+            label.push_str(&format!(" = {}", val));
+        }
+        label
+    }
+
+    fn children(&self) -> &[Self] {
+        &self.children
+    }
+}
+
+// Analiza el programa. Con errores de sintaxis el árbol recuperado se analiza igual; los
+// nodos que insertó el parser (operandos e identificadores faltantes) se ignoran.
+pub fn analyze(program: &Program, options: &SemanticOptions, locale: Locale) -> Analysis {
+    let mut analyzer = Analyzer {
+        locale,
+        options: *options,
+        table: SymbolTable::default(),
+        open_scopes: Vec::new(),
+        types: Vec::new(),
+        diagnostics: Vec::new(),
+        assigned: Some(HashSet::new()),
+        uninitialized: HashSet::new(),
+    };
+    analyzer.enter_scope(ScopeKind::Global, "global", 0);
+    analyzer.statements(&program.items);
+    analyzer.exit_scope();
+    analyzer.report_unused();
+    analyzer.diagnostics.extend(structure::check(program, locale));
+    analyzer.diagnostics.sort_by_key(|diagnostic| (diagnostic.line, diagnostic.column));
+    Analysis {
+        table: analyzer.table,
+        types: analyzer.types,
+        diagnostics: analyzer.diagnostics,
+        tree: None,
+    }
+}
+
+// Árbol anotado: la misma forma que `cst::tree_view`, con el tipo y el valor de cada nodo de
+// expresión. Los nodos se relacionan con `types` por la línea y columna de su token.
+pub fn annotate(root: &SyntaxNode, types: &[TypedExpr]) -> AnnotatedNode {
+    let source = root.text();
+    let line_starts: Vec<usize> = std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let types: HashMap<(usize, usize), &TypedExpr> = types.iter().map(|typed| ((typed.line, typed.column), typed)).collect();
+    let lookup = |offset: usize| -> Option<&TypedExpr> {
+        let line = line_starts.partition_point(|start| *start <= offset);
+        let column = source[line_starts[line - 1]..offset].chars().count() + 1;
+        types.get(&(line, column)).copied()
+    };
+    annotate_node(root, &lookup)
+}
+
+fn annotate_node<'a>(node: &SyntaxNode, lookup: &dyn Fn(usize) -> Option<&'a TypedExpr>) -> AnnotatedNode {
+    let children = node.children();
+    let tokens = node.significant_tokens();
+    let kind = node.kind();
+    let mut annotated = AnnotatedNode {
+        node_type: kind.clone(),
+        token: None,
+        value: None,
+        ty: None,
+        val: None,
+        children: Vec::new(),
+    };
+    // Token que identifica la expresión: el literal o la variable en las hojas, el '(' en las
+    // agrupadas y el operador (hoja del segundo hijo) en las binarias
+    let anchor = match kind {
+        NodeType::Factor if children.is_empty() => {
+            let token = tokens.first();
+            if let Some(TokenKind::Token(token_type)) = token.map(|token| token.kind()) {
+                annotated.token = Some(token_type.clone());
+                annotated.value = token.map(|token| token.text().to_string());
+                match token_type {
+                    TokenType::NumInt | TokenType::NumReal | TokenType::ID => token.map(|token| token.range().0),
+                    _ => None,
+                }
+            } else {
+                None
+            }
+        }
+        NodeType::Factor => tokens.first().map(|token| token.range().0),
+        NodeType::Expression | NodeType::Term => children
+            .get(1)
+            .and_then(|operator| operator.significant_tokens().first().map(|token| token.range().0)),
+        _ => None,
+    };
+    if let Some(typed) = anchor.and_then(lookup) {
+        annotated.ty = Some(typed.ty);
+        annotated.val = typed.val;
+    }
+    annotated.children = children.iter().map(|child| annotate_node(child, lookup)).collect();
+    annotated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::ENGLISH;
+    use crate::parse::parse_source;
+
+    // Códigos de los diagnósticos del programa, en orden
+    fn codes(source: &str) -> Vec<String> {
+        let (result, _) = parse_source(source, &ENGLISH, Locale::Es);
+        assert!(result.errors.is_empty(), "errores de sintaxis en {:?}", source);
+        analyze(&result.ast, &SemanticOptions::default(), Locale::Es)
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect()
+    }
+
+    // Cuerpo de main con dos variables enteras ya asignadas y usadas
+    fn in_main(body: &str) -> Vec<String> {
+        codes(&format!("main() {{\n    int x, y;\n    cin x;\n    {}\n    cout y;\n}}\n", body))
+    }
+
+    fn diagnostics_with(body: &str, options: &SemanticOptions) -> Vec<Diagnostic> {
+        let source = format!("main() {{\n    int x, y;\n    cin x;\n    {}\n    cout y;\n}}\n", body);
+        let (result, _) = parse_source(&source, &ENGLISH, Locale::Es);
+        assert!(result.errors.is_empty(), "errores de sintaxis en {:?}", source);
+        analyze(&result.ast, options, Locale::Es).diagnostics
+    }
+
+    #[test]
+    fn division_by_constant_zero() {
+        assert_eq!(in_main("y = x / 0;"), [codes::DIVISION_BY_ZERO]);
+        assert_eq!(in_main("y = x % 0;"), [codes::DIVISION_BY_ZERO]);
+        assert_eq!(in_main("y = x + 7 / 0;"), [codes::DIVISION_BY_ZERO]);
+        assert_eq!(in_main("y = x / (1 - 1);"), [codes::DIVISION_BY_ZERO]);
+        assert_eq!(in_main("y = x + 7 % (1-1);"), [codes::DIVISION_BY_ZERO]);
+    }
+
+    #[test]
+    fn division_by_unknown_divisor() {
+        assert!(in_main("y = 7 / x;").is_empty());
+        assert!(in_main("y = x % (2 - 1);").is_empty());
+    }
+
+    #[test]
+    fn read_before_assignment() {
+        assert_eq!(codes("main() {\n    int x;\n    cout x;\n}\n"), [codes::UNINITIALIZED_VARIABLE]);
+        assert_eq!(in_main("int z;\n    if x > 0 { z = 1; }\n    y = z;"), [codes::UNINITIALIZED_VARIABLE]);
+        assert!(in_main("int z;\n    if x > 0 { z = 1; } else { z = 2; }\n    y = z;").is_empty());
+    }
+
+    // Las globales empiezan en 0, como en C (ver la explicación de S011)
+    #[test]
+    fn globals_start_at_zero() {
+        assert!(codes("int g;\nmain() {\n    cout g;\n}\n").is_empty());
+    }
+
+    #[test]
+    fn modulo_on_real_is_only_a_type_error() {
+        assert_eq!(in_main("y = x % 0.0;"), [codes::MODULO_ON_REAL]);
+    }
+
+    // Una variable declarada en un bloque deja de existir al cerrarlo
+    #[test]
+    fn block_variables_are_invisible_after_the_block() {
+        assert_eq!(in_main("if (x > 0) { int z; z = x; y = z; }\n    y = z;"), [codes::UNDECLARED_VARIABLE]);
+        assert_eq!(in_main("while (x > 0) { int z; z = 1; x = x - z; }\n    y = z;"), [codes::UNDECLARED_VARIABLE]);
+        assert_eq!(in_main("do { int z; z = 1; x = x - z; } while (x > 0);\n    y = z;"), [codes::UNDECLARED_VARIABLE]);
+    }
+
+    #[test]
+    fn shadowing_points_to_the_outer_declaration() {
+        let diagnostics = diagnostics_with("if (x > 0) { int x; x = 1; y = x; } else { y = 0; }", &SemanticOptions::default());
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        let shadowing = &diagnostics[0];
+        assert_eq!((shadowing.code.as_str(), shadowing.line, shadowing.column, shadowing.length), (codes::SHADOWED_VARIABLE, 4, 22, 1));
+        assert_eq!(shadowing.related.len(), 1);
+        assert_eq!((shadowing.related[0].line, shadowing.related[0].column), (2, 9));
+    }
+
+    #[test]
+    fn shadowing_warning_can_be_disabled() {
+        let options = SemanticOptions { warn_shadowing: false };
+        assert!(diagnostics_with("if (x > 0) { int x; x = 1; y = x; } else { y = 0; }", &options).is_empty());
+        // La redeclaración en el mismo ámbito sigue siendo un error
+        let codes: Vec<String> = diagnostics_with("int y;\n    y = x;", &options).into_iter().map(|diagnostic| diagnostic.code).collect();
+        assert_eq!(codes, [codes::REDECLARED_VARIABLE]);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::ast::{Program, Span, Stmt};
+use crate::codes;
+use crate::diagnostic::Diagnostic;
+use crate::locale::Locale;
+
+// Estructura del programa. La gramática acepta cualquier sentencia en el nivel superior; aquí
+// se exige lo que espera el resto del compilador: exactamente una función main, y fuera de ella
+// solo declaraciones (variables globales). Las sentencias ejecutables van dentro de main.
+
+pub fn check(program: &Program, locale: Locale) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut first_main: Option<Span> = None;
+    for item in &program.items {
+        match item {
+            Stmt::Main { body, span } => {
+                match first_main {
+                    Some(first) => {
+                        let line = first.line.to_string();
+                        diagnostics.push(Diagnostic::at(codes::DUPLICATE_MAIN, locale, *span, 1, &[("line", &line)]));
+                    }
+                    None => first_main = Some(*span),
+                }
+                nested_main(body, locale, &mut diagnostics);
+            }
+            Stmt::Declaration { .. } => {}
+            other => diagnostics.push(Diagnostic::at(codes::STATEMENT_OUTSIDE_MAIN, locale, other.span(), 1, &[])),
+        }
+    }
+    if first_main.is_none() {
+        diagnostics.push(Diagnostic::at(codes::MISSING_MAIN, locale, Span { line: 1, column: 1 }, 1, &[]));
+    }
+    diagnostics
+}
+
+// main dentro de un bloque: la gramática lo acepta como una sentencia más
+fn nested_main(statements: &[Stmt], locale: Locale, diagnostics: &mut Vec<Diagnostic>) {
+    for statement in statements {
+        match statement {
+            Stmt::Main { body, span } => {
+                diagnostics.push(Diagnostic::at(codes::NESTED_MAIN, locale, *span, 1, &[]));
+                nested_main(body, locale, diagnostics);
+            }
+            Stmt::If { then, else_, .. } => {
+                nested_main(then, locale, diagnostics);
+                if let Some(else_) = else_ {
+                    nested_main(else_, locale, diagnostics);
+                }
+            }
+            Stmt::While { body, .. } | Stmt::DoWhile { body, .. } | Stmt::RepeatUntil { body, .. } => {
+                nested_main(body, locale, diagnostics);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::ENGLISH;
+    use crate::parse::parse_source;
+
+    // Código, línea y columna de cada diagnóstico del programa, en orden
+    fn diagnostics(source: &str) -> Vec<(String, usize, usize)> {
+        let (result, _) = parse_source(source, &ENGLISH, Locale::Es);
+        assert!(result.errors.is_empty(), "errores de sintaxis en {:?}", source);
+        check(&result.ast, Locale::Es)
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.line, diagnostic.column))
+            .collect()
+    }
+
+    fn at(code: &str, line: usize, column: usize) -> (String, usize, usize) {
+        (code.to_string(), line, column)
+    }
+
+    #[test]
+    fn globals_and_one_main_are_valid() {
+        assert_eq!(diagnostics("int g;\ndouble h;\nmain() {\n    g = 1;\n}\n"), []);
+    }
+
+    #[test]
+    fn missing_main() {
+        assert_eq!(diagnostics("int g;\n"), [at(codes::MISSING_MAIN, 1, 1)]);
+        assert_eq!(diagnostics(""), [at(codes::MISSING_MAIN, 1, 1)]);
+    }
+
+    #[test]
+    fn duplicate_main_points_to_the_first() {
+        let source = "main() {\n    cout 1;\n}\n\nmain() {\n    cout 2;\n}\n";
+        assert_eq!(diagnostics(source), [at(codes::DUPLICATE_MAIN, 5, 1)]);
+        let (result, _) = parse_source(source, &ENGLISH, Locale::Es);
+        assert_eq!(check(&result.ast, Locale::Es)[0].message, "Error semántico en 5:1: ya hay una función main en la línea 1");
+    }
+
+    #[test]
+    fn statement_outside_main() {
+        assert_eq!(diagnostics("int g;\ng = 1;\nmain() {\n    cout g;\n}\ncout g;\n"), [at(codes::STATEMENT_OUTSIDE_MAIN, 2, 1), at(codes::STATEMENT_OUTSIDE_MAIN, 6, 1)]);
+    }
+
+    // main dentro de otro main o de un bloque anidado
+    #[test]
+    fn nested_main() {
+        assert_eq!(diagnostics("main() {\n    main() {\n        cout 1;\n    }\n}\n"), [at(codes::NESTED_MAIN, 2, 5)]);
+        assert_eq!(diagnostics("main() {\n    int x;\n    cin x;\n    if (x > 0) {\n        while (x > 0) {\n            main() { }\n        }\n    }\n}\n"), [at(codes::NESTED_MAIN, 6, 13)]);
+    }
+}
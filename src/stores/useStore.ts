@@ -1,6 +1,38 @@
 import { ref, computed } from "vue";
 import { defineStore } from "pinia";
 
+// Error con posición devuelto por el backend (ver diagnostic.rs)
+export interface Diagnostic {
+  code: string;
+  severity: "error" | "warning";
+  message: string;
+  line: number;
+  column: number;
+  length: number;
+  expected: string[];
+  found: string | null;
+  snippet: string | null;
+  related: Related[];
+}
+
+// Otra posición que explica el diagnóstico, como la declaración que otra oculta
+export interface Related {
+  message: string;
+  line: number;
+  column: number;
+  length: number;
+  snippet: string | null;
+}
+
+// Cambio en los tokens tras una edición (ver TokenDelta en document.rs)
+export interface TokenDelta {
+  start: number;
+  deleted: number;
+  inserted: string[][];
+  shift: { end_line: number; lines: number; columns: number };
+  errors: string[][];
+}
+
 export const useStore = defineStore({
   id: "main",
   state: () => ({
@@ -14,8 +46,12 @@ export const useStore = defineStore({
     flagEditor: false,
     flagSave: false,
     errors: [] as string[][],
-    errorsSyntax: [] as string[],
+    errorsSyntax: [] as Diagnostic[],
     tokens: [] as string[][],
+    dialect: "english",
+    locale: "es",
+    formatOptions: { indent_width: 4, brace_style: "same-line" },
+    semanticOptions: { warn_shadowing: true },
   }),
   actions: {
     toggleSidebar() {
@@ -49,7 +85,33 @@ export const useStore = defineStore({
     setTokens(tokens: string[][]) {
       this.tokens = tokens;
     },
-    setErrorsSyntax(errors: string[]) {
+    // Aplica un TokenDelta de document.rs: reemplaza los tokens re-escaneados y mueve los
+    // posteriores a la edición (cada token es [tipo, lexema, línea, columna])
+    applyTokenDelta(delta: TokenDelta) {
+      this.tokens.splice(delta.start, delta.deleted, ...delta.inserted);
+      const { end_line, lines, columns } = delta.shift;
+      for (let i = delta.start + delta.inserted.length; i < this.tokens.length; i++) {
+        const token = this.tokens[i] as any[];
+        if (token[2] === end_line) {
+          token[3] += columns;
+        }
+        token[2] += lines;
+      }
+      this.errors = delta.errors;
+    },
+    setDialect(dialect: string | null | undefined) {
+      this.dialect = dialect || "english";
+    },
+    setLocale(locale: string | null | undefined) {
+      this.locale = locale || "es";
+    },
+    setBraceStyle(style: string) {
+      this.formatOptions.brace_style = style;
+    },
+    toggleShadowingWarnings() {
+      this.semanticOptions.warn_shadowing = !this.semanticOptions.warn_shadowing;
+    },
+    setErrorsSyntax(errors: Diagnostic[]) {
       this.errorsSyntax = errors;
     },
     resetErrors() {